@@ -0,0 +1,248 @@
+use crate::board::fit_shape;
+use crate::error::Error;
+use crate::layer::Position;
+use crate::pieces::PieceSet;
+use crate::Layers;
+use std::collections::HashMap;
+
+/// The most open cells a board can have, since every cell is a bit of a `u64`.
+const MAX_OPEN_CELLS: usize = 64;
+
+/// Checks that a board with the specified number of open cells can be represented by
+/// `ShapeMasks`: it needs at least one open cell, and at most one per bit of a `u64`.
+pub(crate) fn check_open_cell_count(count: usize) -> Result<(), Error> {
+    match count {
+        0 => Result::Err(Error::MalformedBoard(
+            "The board does not have any open cells".to_string(),
+        )),
+        count if count > MAX_OPEN_CELLS => Result::Err(Error::MalformedBoard(format!(
+            "The board has {} open cells, but boards may have {} at the most",
+            count, MAX_OPEN_CELLS
+        ))),
+        _ => Result::Ok(()),
+    }
+}
+
+/// Precomputed bitmasks for every orientation of every piece at every position on a board.
+///
+/// Each cell of the board is assigned a bit. Bits are assigned in the same order
+/// `Board::positions` lists the cells: layer by layer, starting with the top left cell of each
+/// layer and moving right and then down. That means the lowest empty bit is always the
+/// top-most, left-most empty cell. Both Kanoodle boards have 55 cells, so a board fits in a
+/// `u64`. Blocked cells (see `BoardMask`) are never filled, so they are not assigned a bit.
+pub struct ShapeMasks {
+    positions: Vec<Position>,
+    /// The index of the first cell of each layer in `bits`.
+    layer_offsets: Vec<usize>,
+    /// The bit assigned to every cell, layer by layer, or `None` if the cell is blocked.
+    bits: Vec<Option<usize>>,
+    /// The number of rows and columns in each layer.
+    layer_dimensions: Vec<(usize, usize)>,
+    full: u64,
+    /// The cells that touch each cell, indexed by the cell's bit.
+    neighbors: Vec<u64>,
+    /// The number of cells in each piece.
+    piece_sizes: Vec<(char, u32)>,
+    /// Keyed by piece name, then indexed by orientation and by the bit of the position the
+    /// orientation is placed at. `None` if the orientation does not fit at that position.
+    masks: HashMap<char, Vec<Vec<Option<u64>>>>,
+}
+
+impl ShapeMasks {
+    /// Computes the masks of the pieces for an empty board with the specified layers. Cells that
+    /// don't have the default value are blocked.
+    ///
+    /// Fails if the board has no open cells, or more than fit in a `u64`.
+    pub fn new<T: Layers<char>>(layers: &T, pieces: &PieceSet) -> Result<ShapeMasks, Error> {
+        let mut positions = Vec::new();
+        let mut layer_offsets = Vec::new();
+        let mut bits = Vec::new();
+        let mut layer_dimensions = Vec::new();
+        for layer in 0..layers.layer_count() {
+            let (row_count, col_count) = layers.dimensions(layer);
+            layer_offsets.push(bits.len());
+            layer_dimensions.push((row_count, col_count));
+            for row in 0..row_count {
+                for col in 0..col_count {
+                    if *layers.at(layer, row, col) == layers.default_cell_value() {
+                        bits.push(Option::Some(positions.len()));
+                        positions.push(Position(layer, row, col));
+                    } else {
+                        bits.push(Option::None);
+                    }
+                }
+            }
+        }
+
+        check_open_cell_count(positions.len())?;
+
+        let mut shape_masks = ShapeMasks {
+            full: u64::MAX >> (64 - positions.len()),
+            neighbors: Vec::new(),
+            piece_sizes: Vec::new(),
+            positions,
+            layer_offsets,
+            bits,
+            layer_dimensions,
+            masks: HashMap::new(),
+        };
+
+        for (name, piece) in pieces.iter() {
+            let orientations = piece
+                .orientations
+                .iter()
+                .map(|shape| {
+                    shape_masks
+                        .positions
+                        .iter()
+                        .map(|position| {
+                            fit_shape(&shape_masks, 0, shape, position)
+                                .ok()
+                                .map(|cells| {
+                                    cells
+                                        .iter()
+                                        .fold(0u64, |mask, cell| mask | shape_masks.bit_of(cell))
+                                })
+                        })
+                        .collect()
+                })
+                .collect();
+            shape_masks.masks.insert(name, orientations);
+        }
+
+        shape_masks.neighbors = (0..shape_masks.positions.len())
+            .map(|index| {
+                shape_masks
+                    .neighbors_of(shape_masks.position_of(index))
+                    .iter()
+                    .fold(0u64, |mask, neighbor| mask | shape_masks.bit_of(neighbor))
+            })
+            .collect();
+
+        shape_masks.piece_sizes = pieces
+            .iter()
+            .map(|(name, piece)| {
+                let size = piece.orientations[0].cell_count().try_into().unwrap();
+                (name, size)
+            })
+            .collect();
+
+        Result::Ok(shape_masks)
+    }
+
+    /// A mask with the bits of every cell of the board set.
+    pub fn full(&self) -> u64 {
+        self.full
+    }
+
+    /// The number of cells on the board.
+    pub fn cell_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layer_dimensions.len()
+    }
+
+    /// The number of rows and columns in the layer.
+    pub fn dimensions(&self, layer: usize) -> (usize, usize) {
+        self.layer_dimensions[layer]
+    }
+
+    /// The bit assigned to the cell at the specified position, which must be open.
+    pub fn index_of(&self, position: &Position) -> usize {
+        self.try_index_of(position)
+            .expect("Only open cells are assigned a bit")
+    }
+
+    /// The bit assigned to the cell at the specified position, or `None` if the cell is blocked
+    /// or not on the board.
+    pub fn try_index_of(&self, position: &Position) -> Option<usize> {
+        let Position(layer, row, col) = *position;
+        let (rows, cols) = *self.layer_dimensions.get(layer)?;
+        if row >= rows || col >= cols {
+            return Option::None;
+        }
+        self.bits[self.layer_offsets[layer] + row * cols + col]
+    }
+
+    /// Determines if the cell at the specified position is on the board and not blocked.
+    pub fn is_open(&self, position: &Position) -> bool {
+        self.try_index_of(position).is_some()
+    }
+
+    pub fn bit_of(&self, position: &Position) -> u64 {
+        1u64 << self.index_of(position)
+    }
+
+    /// The position of the cell assigned to the specified bit.
+    pub fn position_of(&self, index: usize) -> &Position {
+        &self.positions[index]
+    }
+
+    /// The positions of the cells that are set in the mask.
+    pub fn positions_in(&self, mask: u64) -> impl Iterator<Item = &Position> {
+        let mut remaining = mask;
+        std::iter::from_fn(move || {
+            if remaining == 0 {
+                return Option::None;
+            }
+            let index = remaining.trailing_zeros() as usize;
+            remaining &= remaining - 1;
+            Option::Some(&self.positions[index])
+        })
+    }
+
+    /// The cells that touch the cell at the specified bit.
+    pub fn neighbors(&self, index: usize) -> u64 {
+        self.neighbors[index]
+    }
+
+    /// The number of cells in each piece, in piece name order.
+    pub fn piece_sizes(&self) -> &[(char, u32)] {
+        &self.piece_sizes
+    }
+
+    /// Lists the positions of the cells that touch the cell at the specified position. Cells
+    /// touch the cells next to them in the same layer. Layers are stacked like the pyramid: a
+    /// cell rests on the (up to) 4 cells below it, so it also touches those and the (up to) 4
+    /// cells above it.
+    fn neighbors_of(&self, position: &Position) -> Vec<Position> {
+        let Position(layer, row, col) = *position;
+        let mut neighbors = Vec::new();
+        let mut add_if_on_board = |layer: usize, row: usize, col: usize| {
+            if self.is_open(&Position(layer, row, col)) {
+                neighbors.push(Position(layer, row, col));
+            }
+        };
+
+        // Going off the top or left edge wraps around to a huge index, which is never on the
+        // board.
+        add_if_on_board(layer, row.wrapping_sub(1), col);
+        add_if_on_board(layer, row + 1, col);
+        add_if_on_board(layer, row, col.wrapping_sub(1));
+        add_if_on_board(layer, row, col + 1);
+        for r in 0..2 {
+            for c in 0..2 {
+                add_if_on_board(
+                    layer + 1,
+                    (row + r).wrapping_sub(1),
+                    (col + c).wrapping_sub(1),
+                );
+                if layer > 0 {
+                    add_if_on_board(layer - 1, row + r, col + c);
+                }
+            }
+        }
+        neighbors
+    }
+
+    /// The cells filled by the piece in the specified orientation when it is placed at the
+    /// position assigned to `index`, or `None` if it doesn't fit there.
+    pub fn mask_at(&self, name: char, orientation_index: usize, index: usize) -> Option<u64> {
+        self.masks
+            .get(&name)
+            .and_then(|orientations| orientations.get(orientation_index))
+            .and_then(|masks| masks[index])
+    }
+}
@@ -1,42 +1,240 @@
-use crate::{layer::Position, pieces::Shape, Layers};
-use arrayvec::ArrayVec;
+use crate::bitboard::ShapeMasks;
+use crate::board_file::BoardMask;
+use crate::color::{ansi_cell, Colored};
+use crate::error::Error;
+use crate::layer::{GridLayers, Position};
+use crate::pieces::{PieceSet, Shape};
+use crate::{Layers, PieceSuggestion};
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
-const EMPTY_SLOT: char = '·';
+pub(crate) const EMPTY_SLOT: char = '·';
 
-define_layers!(Rectangle, char, EMPTY_SLOT, { layer: 0, rows: 5, cols: 11 });
+/// Fills the cells of a board read from a file that are not part of the board (see
+/// `BoardMask`). It is written as `#` in board states.
+pub(crate) const BLOCKED_SLOT: char = '#';
 
-define_layers!(Pyramid, char, EMPTY_SLOT,
-    { layer: 0, rows: 5, cols: 5 },
-    { layer: 1, rows: 4, cols: 4 },
-    { layer: 2, rows: 3, cols: 3 },
-    { layer: 3, rows: 2, cols: 2 },
-    { layer: 4, rows: 1, cols: 1 }
-);
+/// Returned when a shape cannot be added to a board. Either part of it would extend off the
+/// edge of the board or it would overlap with a shape that has already been placed.
+#[derive(Debug)]
+pub struct DoesNotFit;
 
-/// Defines a Kanoodle board that can be a flat rectangle or a 3d pyramid.
+/// The number of rows and columns in the layer of the rectangle board.
+const RECTANGLE: [(usize, usize); 1] = [(5, 11)];
+
+/// The number of rows and columns in each layer of the pyramid board, from the bottom layer to
+/// the top one.
+const PYRAMID: [(usize, usize); 5] = [(5, 5), (4, 4), (3, 3), (2, 2), (1, 1)];
+
+/// Defines a Kanoodle board: a flat rectangle, a 3d pyramid or any other stack of layers (such as
+/// a shape read from a file). The layers are sized at run time (see `GridLayers`).
 ///
 /// The board keeps track of the currently placed pieces and also the next
 /// possible position a piece may be added.
-pub struct Board<T: Layers<char>> {
-    layers: T,
+///
+/// Every cell of the board is a bit in the `occupied` bitmask (see `ShapeMasks`). Checking if
+/// a piece fits in the next position is a single AND with a precomputed mask. The layers of
+/// chars are only built from the masks of the placed pieces when the board is displayed.
+#[derive(Clone)]
+pub struct Board {
+    board_type: BoardType,
     pub next_pos: Position,
+    occupied: u64,
+    /// The pieces on the board and the cells they fill.
+    placed: Vec<(char, u64)>,
+    masks: Arc<ShapeMasks>,
 }
 
-impl<T: Layers<char>> Board<T> {
+impl Board {
+    /// Creates an empty board of the requested type.
+    pub fn new(board_type: &BoardType) -> Board {
+        let masks = match board_type {
+            BoardType::Custom(mask) => mask.shape_masks(),
+            BoardType::CustomPieces(custom) => custom.shape_masks(),
+            _ => {
+                // The masks take a moment to compute, so they are shared by every board of the
+                // same type.
+                let mut masks = BUILT_IN_MASKS.lock().unwrap();
+                Arc::clone(masks.entry(format!("{:?}", board_type)).or_insert_with(|| {
+                    Arc::new(
+                        ShapeMasks::new(&board_type.empty_layers(), board_type.pieces())
+                            .expect("The built-in boards have between 1 and 64 cells"),
+                    )
+                }))
+            }
+        };
+        Board {
+            board_type: board_type.clone(),
+            next_pos: *masks.position_of(0),
+            occupied: 0,
+            placed: Vec::new(),
+            masks,
+        }
+    }
+
+    pub fn board_type(&self) -> BoardType {
+        self.board_type.clone()
+    }
+
+    /// The pieces the board is filled with (see `BoardType::pieces`).
+    pub fn pieces(&self) -> &Arc<PieceSet> {
+        self.board_type.pieces()
+    }
+
+    pub fn next_pos(&self) -> &Position {
+        &self.next_pos
+    }
+
+    pub fn set_next_pos(&mut self, position: Position) {
+        self.next_pos = position;
+    }
+
     /// Determines if the board is currently in the solved state.
-    /// It is solved if none of the cells in any layer are holding
-    /// the empty slot char.
+    /// It is solved if every cell in every layer is filled.
     pub fn solved(&self) -> bool {
-        self.layers.find(&EMPTY_SLOT).is_none()
+        self.occupied == self.masks.full()
+    }
+
+    /// The cells that are filled, one bit per cell. (See `ShapeMasks`)
+    pub fn occupied(&self) -> u64 {
+        self.occupied
+    }
+
+    /// The cells filled by the piece with the specified name, one bit per cell, or `None` if
+    /// the piece is not on the board.
+    pub fn mask_of(&self, name: char) -> Option<u64> {
+        self.placed
+            .iter()
+            .find(|(letter, _)| *letter == name)
+            .map(|(_, mask)| *mask)
+    }
+
+    /// The precomputed masks the board uses.
+    pub fn masks(&self) -> &ShapeMasks {
+        &self.masks
+    }
+
+    /// Determines if the board can no longer be solved because of a dead region. A region is a
+    /// group of empty cells that touch each other (see `ShapeMasks::neighbors`). Pieces cannot
+    /// reach into other regions, so a region is dead if no combination of the pieces that are not
+    /// on the board has the same number of cells. For example, a region of 1 or 2 cells is
+    /// always dead.
+    pub fn has_dead_region(&self) -> bool {
+        // Bit n is set if some combination of the remaining pieces has n cells in total.
+        let mut sizes = 1u64;
+        for (name, size) in self.masks.piece_sizes() {
+            if !self.placed.iter().any(|(letter, _)| letter == name) {
+                sizes |= sizes << size;
+            }
+        }
+
+        let mut empty = self.masks.full() & !self.occupied;
+        while empty != 0 {
+            // Flood the region that contains the lowest empty cell.
+            let mut region = empty & empty.wrapping_neg();
+            let mut added = region;
+            while added != 0 {
+                let mut touching = 0u64;
+                while added != 0 {
+                    touching |= self.masks.neighbors(added.trailing_zeros() as usize);
+                    added &= added - 1;
+                }
+                added = touching & empty & !region;
+                region |= added;
+            }
+
+            if sizes.checked_shr(region.count_ones()).unwrap_or(0) & 1 == 0 {
+                return true;
+            }
+            empty &= !region;
+        }
+        false
+    }
+
+    /// Returns the letter of the piece filling the cell at the specified position, or `None`
+    /// if the cell is empty (or blocked).
+    pub fn letter_at(&self, position: &Position) -> Option<char> {
+        let bit = 1u64 << self.masks.try_index_of(position)?;
+        self.placed
+            .iter()
+            .find(|(_, mask)| mask & bit != 0)
+            .map(|(letter, _)| *letter)
+    }
+
+    /// The layers of the board with the letter of the piece filling each cell, `EMPTY_SLOT` in
+    /// the empty cells and `BLOCKED_SLOT` in the blocked ones.
+    fn letters(&self) -> GridLayers<char> {
+        let mut layers = self.board_type.empty_layers();
+        for (letter, mask) in &self.placed {
+            for pos in self.masks.positions_in(*mask) {
+                layers.update(pos.0, pos.1, pos.2, *letter);
+            }
+        }
+        layers
+    }
+
+    /// The board in the same format as an initial state (see `Shape::parse`): one string per
+    /// layer, with rows separated by new lines, `.` in the empty cells and `#` in the blocked
+    /// ones.
+    pub fn state(&self) -> Vec<String> {
+        let layers = self.letters();
+        (0..layers.layer_count())
+            .map(|layer| {
+                let (row_count, col_count) = layers.dimensions(layer);
+                (0..row_count)
+                    .map(|row| {
+                        (0..col_count)
+                            .map(|col| match *layers.at(layer, row, col) {
+                                EMPTY_SLOT => '.',
+                                letter => letter,
+                            })
+                            .collect::<String>()
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            })
+            .collect()
+    }
+
+    /// Lists the positions of every cell on the board, layer by layer, starting with the top
+    /// left cell of each layer and moving right and then down. Blocked cells are left out.
+    pub fn positions(&self) -> Vec<Position> {
+        self.masks
+            .positions_in(self.masks.full())
+            .copied()
+            .collect()
+    }
+
+    /// Adds the suggested piece to the board in the next open position. This is the fast path
+    /// used by the solvers. It relies on the masks precomputed for the piece's orientation.
+    ///
+    /// If it does not fit, an error will be returned.
+    pub fn try_add_piece(&mut self, piece: &PieceSuggestion) -> Result<(), DoesNotFit> {
+        let mask = self
+            .masks
+            .mask_at(
+                piece.name,
+                piece.orientation_index(),
+                self.masks.index_of(&self.next_pos),
+            )
+            .ok_or(DoesNotFit)?;
+
+        if self.occupied & mask != 0 {
+            return Result::Err(DoesNotFit);
+        }
+
+        self.fill(piece.name, mask);
+        Result::Ok(())
     }
 
     /// Adds the specified shape to the board in the next open position.
     ///
     /// If it does not fit, an error will be returned.
-    pub fn try_add_shape(&mut self, shape: &Shape, letter: char) -> Result<(), ()> {
+    pub fn try_add_shape(&mut self, shape: &Shape, letter: char) -> Result<(), DoesNotFit> {
         let position = Position(self.next_pos.0, self.next_pos.1, self.next_pos.2);
         self.try_add_shape_at(shape, letter, &position)
     }
@@ -49,148 +247,76 @@ impl<T: Layers<char>> Board<T> {
         shape: &Shape,
         letter: char,
         position: &Position,
-    ) -> Result<(), ()> {
-        // Keep mutable references to the cells that we need to updated with the letter
-        // if we find the shape actually fits.
-        let mut positions_to_update: ArrayVec<Position, 5> = ArrayVec::new();
-
-        // We'll call this macro if the shape fits. It's not a lambda to avoid borrowing issues
-        macro_rules! add_shape {
-            ($self:ident, $positions_to_update:ident, $letter:ident, $empty_slot:ident) => {{
-                for pos in $positions_to_update {
-                    $self.layers.update(pos.0, pos.1, pos.2, $letter);
-                }
-                $self.next_pos = $self.layers.find(&$empty_slot).unwrap_or(Position(0, 0, 0));
-                Result::Ok(())
-            }};
-        }
-
-        // Shapes may not be aligned such that their (0, 0) cell is set, but we must always add
-        // the shape to the board in a way that fills the next_pos' position. Therefore, we need
-        // to know how far we'll need to shift the shape when placing it on the board.
-        let (shape_row_offset, shape_col_offset) = calculate_shape_offsets(shape);
-
-        let board_layer_count = self.layers.layer_count();
-        let Position(layer, next_row, next_col) = position;
-
-        // The shape and board layers are not necessarily equal. If we are trying to place a piece
-        // where the next_pos layer is 2, the 0-layer of the shape will be matched against the 2-layer
-        // of the board. 1 -> 3, 2 -> 4, etc. If there are any parts of the shape in its 3-layer, the
-        // shape will not fit. We'll loop over the shape layers because those are what we need to
-        // add to the board layers.
-        for shape_layer_index in 0..shape.layer_count() {
-            // As shape layers increase, we must also increase the board layer. Remember, they layers
-            // must be kept in sync, but are not necessarily equal.
-            let board_layer = layer + shape_layer_index;
-            let (shape_size, _) = shape.dimensions(shape_layer_index);
-
-            // Check if we've run out of layers on the board.
-            if board_layer >= board_layer_count {
-                // We've run out of layers, we'll know the shape doesn't fit if there is any part of
-                // it in this next shape layer. Below is an example of attempting to place a 3d shape
-                // starting on layer 2 of the pyramid board. At the last board layer (4) there are
-                // still two shape layers (3 & 4) which have parts of the shape.
-                //
-                //                        C     <-< 4 shape layers
-                //                         C    <-< 3 <-- The layer being checked in this if block
-                // board layers 4 >->     C     <-< 2
-                //              3 >->    C      <-< 1
-                //              2 >->   A       <-< 0
-                //              1 >->  A A K K
-                //              0 >-> A B B B J
-                for shape_row in 0..shape_size {
-                    for shape_col in 0..shape_size {
-                        if shape.is_set(shape_layer_index, shape_row, shape_col) {
-                            // We found a piece of the shape in the layer, therefore
-                            // this shape cannot fit on the board.
-                            return Result::Err(());
-                        }
-                    }
-                }
-                // Shapes are contiguous, and they always start at the lowest layer.
-                // If we get this point, there was no part of the shape in this layer
-                // and there cannot be any part of the shape on a higher layer either.
-                // Therefore, we can say the shape fits.
-                return add_shape!(self, positions_to_update, letter, EMPTY_SLOT);
-            }
-
-            // We have at least one board layer in which we can see if the shape fits.
-
-            let (board_row_count, board_col_count) = self.layers.dimensions(board_layer);
+    ) -> Result<(), DoesNotFit> {
+        let mask = self
+            .fit_shape_at(shape, position)?
+            .iter()
+            .fold(0u64, |mask, pos| mask | self.masks.bit_of(pos));
+        self.fill(letter, mask);
+        Result::Ok(())
+    }
 
-            let mut no_parts_found_in_layer = true;
+    /// Marks the cells in the mask as filled by the letter.
+    fn fill(&mut self, letter: char, mask: u64) {
+        self.occupied |= mask;
+        self.placed.push((letter, mask));
 
-            // Starting with the shape, for every cell that is part of the shape, we must find a
-            // corresponding empty slot in the layer. If any part of the shape in this layer does
-            // not have a corresponding empty slot in the layer, the shape does not fit.
-            for shape_row in 0..shape_size {
-                for shape_col in 0..shape_size {
-                    if shape.is_set(shape_layer_index, shape_row, shape_col) {
-                        // If the numbers go negative they'll wrap around and be much greater than the board
-                        // or column count. This takes the place of checking for < 0.
-                        let board_row = (next_row + shape_row).wrapping_sub(shape_row_offset);
-                        let board_col = (next_col + shape_col).wrapping_sub(shape_col_offset);
-
-                        if board_row >= board_row_count || board_col >= board_col_count {
-                            // Our shape does not fit in the available space on this layer. There is at least
-                            // one part of it that would be extend off the edge of the board if we tried to
-                            // place it.
-                            return Result::Err(());
-                        }
-                        if *self.layers.at(board_layer, board_row, board_col) != EMPTY_SLOT {
-                            // Our shape does not fit here. There is at least on part of it that would overlap
-                            // with an existing shape already on the board.
-                            return Result::Err(());
-                        }
-
-                        no_parts_found_in_layer = false;
-                        positions_to_update.push(Position(board_layer, board_row, board_col));
-                    }
-                }
-            }
-
-            if !shape.is_3d || no_parts_found_in_layer {
-                // If the shape isn't 3d or the shape does not have any parts in the layer, that
-                // means we can skip the remaining layers. We know the shape must fit.
-                return add_shape!(self, positions_to_update, letter, EMPTY_SLOT);
-            }
-        }
+        let empty = self.masks.full() & !self.occupied;
+        self.next_pos = if empty == 0 {
+            *self.masks.position_of(0)
+        } else {
+            *self.masks.position_of(empty.trailing_zeros() as usize)
+        };
+    }
 
-        // All our checks passed successfully! The shape fits.
-        return add_shape!(self, positions_to_update, letter, EMPTY_SLOT);
+    /// Finds the cells the specified shape would fill if it was added to the board at the
+    /// specified position. The board is not changed.
+    ///
+    /// If it does not fit, an error will be returned.
+    pub fn fit_shape_at(
+        &self,
+        shape: &Shape,
+        position: &Position,
+    ) -> Result<Vec<Position>, DoesNotFit> {
+        fit_shape(&self.masks, self.occupied, shape, position)
     }
 
     /// Removes the space with the specified name from the board (if it is present).
     /// This method will do nothing if the shape is not present on the board.
     pub fn remove_shape(&mut self, name: char) {
-        let mut updated_next_pos = false;
-        let layer_count = self.layers.layer_count();
-        for layer in 0..layer_count {
-            let (row_count, col_count) = self.layers.dimensions(layer);
-            for row in 0..row_count {
-                for col in 0..col_count {
-                    if *self.layers.at(layer, row, col) == name {
-                        self.layers.update(layer, row, col, EMPTY_SLOT);
-                        if !updated_next_pos {
-                            updated_next_pos = true;
-                            self.next_pos = Position(layer, row, col);
-                        }
-                    }
-                }
-            }
+        if let Option::Some(index) = self.placed.iter().position(|(letter, _)| *letter == name) {
+            let (_, mask) = self.placed.remove(index);
+            self.occupied &= !mask;
+            // The first cell of the removed shape is where the next piece goes.
+            self.next_pos = *self.masks.position_of(mask.trailing_zeros() as usize);
         }
     }
 }
 
-impl<T: Layers<char>> fmt::Display for Board<T> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut layer = self.layers.layer_count() - 1;
+impl Board {
+    /// Displays the board with a background color per piece (see `color::piece_color`).
+    pub fn colored(&self) -> Colored<'_, Self> {
+        Colored {
+            value: self,
+            letter: EMPTY_SLOT,
+            pieces: self.pieces(),
+        }
+    }
+
+    /// Writes the layers from the top of the pyramid down, indenting each layer so it sits on
+    /// the layer below it. `cell` formats every cell, except blocked ones, which are left blank.
+    fn fmt_cells(&self, f: &mut Formatter<'_>, cell: impl Fn(char) -> String) -> fmt::Result {
+        let layers = self.letters();
+        let mut layer = layers.layer_count() - 1;
         loop {
-            let (row_count, col_count) = self.layers.dimensions(layer);
+            let (row_count, col_count) = layers.dimensions(layer);
             for row in 0..row_count {
                 write!(f, "{:width$}", "", width = layer)?;
                 for col in 0..col_count {
-                    write!(f, " {}", self.layers.at(layer, row, col))?;
+                    match *layers.at(layer, row, col) {
+                        BLOCKED_SLOT => write!(f, "  ")?,
+                        letter => write!(f, "{}", cell(letter))?,
+                    }
                 }
                 writeln!(f, " ")?;
             }
@@ -203,108 +329,346 @@ impl<T: Layers<char>> fmt::Display for Board<T> {
     }
 }
 
-impl Board<Rectangle> {
-    pub fn new() -> Self {
-        Board {
-            layers: Rectangle::default(),
-            next_pos: Position(0, 0, 0),
-        }
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_cells(f, |letter| format!(" {}", letter))
     }
 }
 
-impl Board<Pyramid> {
-    pub fn new() -> Self {
-        Board {
-            layers: Pyramid::default(),
-            next_pos: Position(0, 0, 0),
+impl fmt::Display for Colored<'_, Board> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.value.fmt_cells(f, |letter| {
+            if letter == EMPTY_SLOT {
+                format!(" {}", letter)
+            } else {
+                ansi_cell(letter, self.pieces, &format!(" {}", letter))
+            }
+        })
+    }
+}
+
+/// The masks of the built-in board types, keyed by the board type's name.
+static BUILT_IN_MASKS: Lazy<Mutex<HashMap<String, Arc<ShapeMasks>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Displays a board with the cells of a single piece highlighted (see `Board::highlighted`).
+pub struct Highlighted<'a> {
+    board: &'a Board,
+    letter: char,
+    color: bool,
+}
+
+impl Board {
+    /// Displays the board with the cells of the piece highlighted, such as a piece that was just
+    /// added. With color, only the piece has a background color (see `color::piece_color`).
+    /// Without color, its cells are marked with `*`.
+    pub fn highlighted(&self, letter: char, color: bool) -> Highlighted<'_> {
+        Highlighted {
+            board: self,
+            letter,
+            color,
         }
     }
 }
 
-pub enum Variation {
-    Rectangle(Board<Rectangle>),
-    Pyramid(Board<Pyramid>),
+impl Display for Highlighted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.board.fmt_cells(f, |letter: char| {
+            if letter != self.letter {
+                format!(" {}", letter)
+            } else if self.color {
+                ansi_cell(letter, self.board.pieces(), &format!(" {}", letter))
+            } else {
+                " *".to_string()
+            }
+        })
+    }
+}
+
+/// The boards the solver can fill. Most of them are filled with the Kanoodle pieces, but the
+/// pentomino rectangles are filled with the 12 pentominoes, and any board can be filled with a
+/// different set (see `BoardType::with_pieces`).
+///
+/// The pentomino rectangles have 2339 (`6 x 10`), 1010 (`5 x 12`), 368 (`4 x 15`) and 2 (`3 x 20`)
+/// solutions, not counting mirror images and rotations of a solution:
+///
+/// ```
+/// # use kanoodle_solver::{Board, BoardType, DlxSolver, Symmetry};
+/// # use std::sync::Arc;
+/// let board_type = BoardType::Pentomino3x20;
+/// let symmetry = Arc::new(Symmetry::new(&Board::new(&board_type)));
+/// let solver = DlxSolver::new(&board_type, None).unwrap().with_symmetry(symmetry);
+/// assert_eq!(solver.count(), 2);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoardType {
+    Rectangle,
+    Pyramid,
+    /// The 12 pentominoes on a `6 x 10` rectangle.
+    Pentomino6x10,
+    /// The 12 pentominoes on a `5 x 12` rectangle.
+    Pentomino5x12,
+    /// The 12 pentominoes on a `4 x 15` rectangle.
+    Pentomino4x15,
+    /// The 12 pentominoes on a `3 x 20` rectangle.
+    Pentomino3x20,
+    /// A board read from a file (see `BoardMask`). It can't be parsed from a string.
+    Custom(Arc<BoardMask>),
+    /// Another board type filled with a different piece set (see `BoardType::with_pieces`). It
+    /// can't be parsed from a string.
+    CustomPieces(Arc<CustomPieces>),
 }
 
-/// This sucks... I haven't really figured out the right way to handle
-/// generic structs. In a language like Java, I'd be able to use a type
-/// like Board<?> to hold the board and then it would dispatch to the
-/// right class at run time. That doesn't seem to be possible in Rust
-/// given the way I've implemented the Rectangle and Pyramid boards.
-/// They have different sizes so I can't have a type that can point to
-/// either impl types. Seems like I've got to do some Boxing or something.
-/// This enum is basically a hand-rolled box anyway...
-impl Variation {
-    pub fn solved(&self) -> bool {
+impl BoardType {
+    /// The layers of an empty board of this type. Blocked cells (see `BoardMask`) are filled
+    /// with `BLOCKED_SLOT`.
+    pub fn empty_layers(&self) -> GridLayers<char> {
         match self {
-            Variation::Rectangle(r) => r.solved(),
-            Variation::Pyramid(p) => p.solved(),
+            BoardType::Rectangle => GridLayers::new(&RECTANGLE, EMPTY_SLOT),
+            BoardType::Pyramid => GridLayers::new(&PYRAMID, EMPTY_SLOT),
+            BoardType::Pentomino6x10 => GridLayers::new(&[(6, 10)], EMPTY_SLOT),
+            BoardType::Pentomino5x12 => GridLayers::new(&[(5, 12)], EMPTY_SLOT),
+            BoardType::Pentomino4x15 => GridLayers::new(&[(4, 15)], EMPTY_SLOT),
+            BoardType::Pentomino3x20 => GridLayers::new(&[(3, 20)], EMPTY_SLOT),
+            BoardType::Custom(mask) => mask.empty_layers(),
+            BoardType::CustomPieces(custom) => custom.board.empty_layers(),
         }
     }
 
-    pub fn try_add_shape(&mut self, shape: &Shape, letter: char) -> Result<(), ()> {
+    /// Determines if pieces may be placed in their 3d orientations, which is only possible on
+    /// boards with more than one layer.
+    pub fn allows_3d_orientations(&self) -> bool {
         match self {
-            Variation::Rectangle(r) => r.try_add_shape(shape, letter),
-            Variation::Pyramid(p) => p.try_add_shape(shape, letter),
+            BoardType::Pyramid => true,
+            BoardType::Custom(mask) => mask.layer_count() > 1,
+            BoardType::CustomPieces(custom) => custom.board.allows_3d_orientations(),
+            _ => false,
         }
     }
 
-    pub fn try_add_shape_at(
-        &mut self,
-        shape: &Shape,
-        letter: char,
-        position: &Position,
-    ) -> Result<(), ()> {
+    /// The pieces the board is filled with. They are shared by every board and placement state of
+    /// this type.
+    pub fn pieces(&self) -> &Arc<PieceSet> {
         match self {
-            Variation::Rectangle(r) => r.try_add_shape_at(shape, letter, position),
-            Variation::Pyramid(p) => p.try_add_shape_at(shape, letter, position),
+            BoardType::Pentomino6x10
+            | BoardType::Pentomino5x12
+            | BoardType::Pentomino4x15
+            | BoardType::Pentomino3x20 => PieceSet::pentominoes(),
+            BoardType::CustomPieces(custom) => &custom.pieces,
+            _ => PieceSet::kanoodle(),
         }
     }
 
-    pub fn remove_shape(&mut self, name: char) {
-        match self {
-            Variation::Rectangle(r) => r.remove_shape(name),
-            Variation::Pyramid(p) => p.remove_shape(name),
+    /// The same board filled with a different piece set, such as one read from a file (see
+    /// `PieceSet::parse`). The name is used when the board type is displayed, such as the name of
+    /// the file.
+    pub fn with_pieces(self, pieces: PieceSet, name: &str) -> BoardType {
+        let board = match self {
+            BoardType::CustomPieces(custom) => custom.board.clone(),
+            board => board,
+        };
+        BoardType::CustomPieces(Arc::new(CustomPieces {
+            board,
+            pieces: Arc::new(pieces),
+            name: name.to_string(),
+            masks: OnceCell::new(),
+        }))
+    }
+
+    /// Checks that the pieces (see `BoardType::pieces`) can fill the board. Every piece is placed
+    /// in a solution, so the board needs exactly as many open cells as the pieces have.
+    pub fn check_cell_count(&self) -> Result<(), Error> {
+        let open_cells = Board::new(self).masks().cell_count();
+        let piece_cells = self.pieces().cell_count();
+        if open_cells == piece_cells {
+            Result::Ok(())
+        } else {
+            Result::Err(Error::CellCountMismatch {
+                open_cells,
+                piece_cells,
+            })
         }
     }
 }
 
-impl Display for Variation {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            Variation::Rectangle(r) => write!(f, "{}", r),
-            Variation::Pyramid(p) => write!(f, "{}", p),
-        }
+/// A board type filled with a different piece set (see `BoardType::with_pieces`).
+pub struct CustomPieces {
+    board: BoardType,
+    pieces: Arc<PieceSet>,
+    name: String,
+    /// Computed the first time a board is created, since they take a moment to compute.
+    masks: OnceCell<Arc<ShapeMasks>>,
+}
+
+impl CustomPieces {
+    /// The board type that is filled with the pieces.
+    pub fn board(&self) -> &BoardType {
+        &self.board
+    }
+
+    /// The name the pieces were read with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The masks shared by every board of this type.
+    fn shape_masks(&self) -> Arc<ShapeMasks> {
+        Arc::clone(self.masks.get_or_init(|| {
+            Arc::new(
+                ShapeMasks::new(&self.board.empty_layers(), &self.pieces)
+                    .expect("The board's open cells were counted when it was read"),
+            )
+        }))
     }
 }
 
-// Creates a new board of the requested type.
-pub fn create_board(board_type: &BoardType) -> Variation {
-    return if *board_type == BoardType::Rectangle {
-        Variation::Rectangle(<Board<Rectangle>>::new())
-    } else {
-        Variation::Pyramid(<Board<Pyramid>>::new())
-    };
+/// Boards with the same piece set are the same board type.
+impl PartialEq for CustomPieces {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board
+            && self.name == other.name
+            && Arc::ptr_eq(&self.pieces, &other.pieces)
+    }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum BoardType {
-    Rectangle,
-    Pyramid,
+/// The board type displays as `CustomPieces(Rectangle with pieces.txt)`.
+impl fmt::Debug for CustomPieces {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} with {}", self.board, self.name)
+    }
 }
 
 impl FromStr for BoardType {
     type Err = String;
 
+    /// Names are not case sensitive and may have dashes, such as `pentomino-6x10` (or
+    /// `Pentomino6x10`, the way the board type is displayed). Any other name is the rectangle.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.eq_ignore_ascii_case("pyramid") {
-            Result::Ok(BoardType::Pyramid)
-        } else {
-            Result::Ok(BoardType::Rectangle)
+        match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "pyramid" => Result::Ok(BoardType::Pyramid),
+            "pentomino6x10" => Result::Ok(BoardType::Pentomino6x10),
+            "pentomino5x12" => Result::Ok(BoardType::Pentomino5x12),
+            "pentomino4x15" => Result::Ok(BoardType::Pentomino4x15),
+            "pentomino3x20" => Result::Ok(BoardType::Pentomino3x20),
+            _ => Result::Ok(BoardType::Rectangle),
         }
     }
 }
 
+/// Finds the cells the specified shape would fill if it was added to a board at the specified
+/// position. Cells are empty if they are open (see `ShapeMasks::try_index_of`) and their bit is
+/// not set in `occupied`. This is the reference implementation that the precomputed masks of
+/// `ShapeMasks` are built from.
+///
+/// If it does not fit, an error will be returned.
+pub(crate) fn fit_shape(
+    masks: &ShapeMasks,
+    occupied: u64,
+    shape: &Shape,
+    position: &Position,
+) -> Result<Vec<Position>, DoesNotFit> {
+    // Keep track of the cells that we need to updated with the letter if we find the shape
+    // actually fits.
+    let mut positions_to_update: Vec<Position> = Vec::new();
+
+    // Shapes may not be aligned such that their (0, 0) cell is set, but we must always add
+    // the shape to the board in a way that fills the next_pos' position. Therefore, we need
+    // to know how far we'll need to shift the shape when placing it on the board.
+    let (shape_row_offset, shape_col_offset) = calculate_shape_offsets(shape);
+
+    let board_layer_count = masks.layer_count();
+    let Position(layer, next_row, next_col) = position;
+
+    // The shape and board layers are not necessarily equal. If we are trying to place a piece
+    // where the next_pos layer is 2, the 0-layer of the shape will be matched against the 2-layer
+    // of the board. 1 -> 3, 2 -> 4, etc. If there are any parts of the shape in its 3-layer, the
+    // shape will not fit. We'll loop over the shape layers because those are what we need to
+    // add to the board layers.
+    for shape_layer_index in 0..shape.layer_count() {
+        // As shape layers increase, we must also increase the board layer. Remember, they layers
+        // must be kept in sync, but are not necessarily equal.
+        let board_layer = layer + shape_layer_index;
+        let (shape_size, _) = shape.dimensions(shape_layer_index);
+
+        // Check if we've run out of layers on the board.
+        if board_layer >= board_layer_count {
+            // We've run out of layers, we'll know the shape doesn't fit if there is any part of
+            // it in this next shape layer. Below is an example of attempting to place a 3d shape
+            // starting on layer 2 of the pyramid board. At the last board layer (4) there are
+            // still two shape layers (3 & 4) which have parts of the shape.
+            //
+            //                        C     <-< 4 shape layers
+            //                         C    <-< 3 <-- The layer being checked in this if block
+            // board layers 4 >->     C     <-< 2
+            //              3 >->    C      <-< 1
+            //              2 >->   A       <-< 0
+            //              1 >->  A A K K
+            //              0 >-> A B B B J
+            for shape_row in 0..shape_size {
+                for shape_col in 0..shape_size {
+                    if shape.is_set(shape_layer_index, shape_row, shape_col) {
+                        // We found a piece of the shape in the layer, therefore
+                        // this shape cannot fit on the board.
+                        return Result::Err(DoesNotFit);
+                    }
+                }
+            }
+            // Shapes are contiguous, and they always start at the lowest layer.
+            // If we get this point, there was no part of the shape in this layer
+            // and there cannot be any part of the shape on a higher layer either.
+            // Therefore, we can say the shape fits.
+            return Result::Ok(positions_to_update);
+        }
+
+        // We have at least one board layer in which we can see if the shape fits.
+
+        let (board_row_count, board_col_count) = masks.dimensions(board_layer);
+
+        let mut no_parts_found_in_layer = true;
+
+        // Starting with the shape, for every cell that is part of the shape, we must find a
+        // corresponding empty slot in the layer. If any part of the shape in this layer does
+        // not have a corresponding empty slot in the layer, the shape does not fit.
+        for shape_row in 0..shape_size {
+            for shape_col in 0..shape_size {
+                if shape.is_set(shape_layer_index, shape_row, shape_col) {
+                    // If the numbers go negative they'll wrap around and be much greater than the board
+                    // or column count. This takes the place of checking for < 0.
+                    let board_row = (next_row + shape_row).wrapping_sub(shape_row_offset);
+                    let board_col = (next_col + shape_col).wrapping_sub(shape_col_offset);
+
+                    if board_row >= board_row_count || board_col >= board_col_count {
+                        // Our shape does not fit in the available space on this layer. There is at least
+                        // one part of it that would be extend off the edge of the board if we tried to
+                        // place it.
+                        return Result::Err(DoesNotFit);
+                    }
+                    let is_empty = masks
+                        .try_index_of(&Position(board_layer, board_row, board_col))
+                        .is_some_and(|index| occupied & (1u64 << index) == 0);
+                    if !is_empty {
+                        // Our shape does not fit here. There is at least on part of it that would overlap
+                        // with an existing shape already on the board (or a blocked cell).
+                        return Result::Err(DoesNotFit);
+                    }
+
+                    no_parts_found_in_layer = false;
+                    positions_to_update.push(Position(board_layer, board_row, board_col));
+                }
+            }
+        }
+
+        if !shape.is_3d || no_parts_found_in_layer {
+            // If the shape isn't 3d or the shape does not have any parts in the layer, that
+            // means we can skip the remaining layers. We know the shape must fit.
+            return Result::Ok(positions_to_update);
+        }
+    }
+
+    // All our checks passed successfully! The shape fits.
+    Result::Ok(positions_to_update)
+}
+
 fn calculate_shape_offsets(shape: &Shape) -> (usize, usize) {
     // Shapes may not be aligned to the top left still (3d shapes
     // in certain orientations cannot be represented in the top left
@@ -323,8 +687,9 @@ fn calculate_shape_offsets(shape: &Shape) -> (usize, usize) {
 
     // The first part of the shape we find starting from the top left and going
     // right and down is equal to the offset.
-    'offsets: for row in 0..5 {
-        for col in 0..5 {
+    let (row_count, col_count) = shape.dimensions(0);
+    'offsets: for row in 0..row_count {
+        for col in 0..col_count {
             if shape.is_set(0, row, col) {
                 shape_row_offset = row;
                 shape_col_offset = col;
@@ -0,0 +1,199 @@
+use crate::bitboard::{check_open_cell_count, ShapeMasks};
+use crate::board::{BLOCKED_SLOT, EMPTY_SLOT};
+use crate::error::Error;
+use crate::layer::{GridLayers, Layers};
+use crate::pieces::PieceSet;
+use once_cell::sync::OnceCell;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// The shape of a board read from a text file, such as a rectangle with blocked cells, an
+/// L-shaped tray or a smaller practice board.
+///
+/// `#` is a blocked cell and `.` is an open cell. New lines separate rows and empty lines
+/// separate layers, from the bottom layer to the top one, the same way board states are written:
+///
+/// ```text
+/// ######.....
+/// ######.....
+/// ...........
+/// ...........
+/// ...........
+/// ```
+///
+/// Rows may be shorter than the longest row of their layer. The cells missing at the end are
+/// blocked. Layers are stacked like the pyramid: every cell rests on the 4 cells below it (the
+/// cell in the same row and column, and the cells to the right and below that one), so a cell
+/// in a higher layer may only be open if those 4 cells are open too.
+pub struct BoardMask {
+    name: String,
+    /// Whether every cell is open, by layer, row and column.
+    open: Vec<Vec<Vec<bool>>>,
+    /// Computed the first time a board is created, since they take a moment to compute.
+    masks: OnceCell<Arc<ShapeMasks>>,
+}
+
+impl BoardMask {
+    /// Reads a board shape (see `BoardMask`). The name is used when the board type is displayed,
+    /// such as the name of the file. Errors name the line and column (both counted from 1).
+    pub fn parse(input: &str, name: &str) -> Result<BoardMask, Error> {
+        let invalid = |line: usize, column: usize, message: String| Error::InvalidBoardFile {
+            line,
+            column,
+            message,
+        };
+
+        let input = input.replace("\r\n", "\n");
+        let mut open: Vec<Vec<Vec<bool>>> = Vec::new();
+        let mut layer: Vec<Vec<bool>> = Vec::new();
+        // The line every row was read from, by layer and row.
+        let mut lines: Vec<Vec<usize>> = Vec::new();
+        let mut layer_lines: Vec<usize> = Vec::new();
+        for (index, text) in input.trim_end_matches('\n').split('\n').enumerate() {
+            let text = text.trim_end();
+            if text.is_empty() {
+                // Layers are separated by one (or more) empty lines.
+                if !layer.is_empty() {
+                    open.push(std::mem::take(&mut layer));
+                    lines.push(std::mem::take(&mut layer_lines));
+                }
+                continue;
+            }
+
+            let mut row = Vec::new();
+            for (col, ch) in text.chars().enumerate() {
+                match ch {
+                    '.' => row.push(true),
+                    '#' => row.push(false),
+                    _ => {
+                        return Result::Err(invalid(
+                            index + 1,
+                            col + 1,
+                            format!(
+                            "Unexpected {} (expected # for a blocked cell or . for an open cell)",
+                            ch
+                        ),
+                        ))
+                    }
+                }
+            }
+            layer.push(row);
+            layer_lines.push(index + 1);
+        }
+        if !layer.is_empty() {
+            open.push(layer);
+            lines.push(layer_lines);
+        }
+
+        // Short rows are filled with blocked cells.
+        for layer in &mut open {
+            let cols = layer.iter().map(|row| row.len()).max().unwrap_or(0);
+            for row in layer.iter_mut() {
+                row.resize(cols, false);
+            }
+        }
+
+        for index in 1..open.len() {
+            let (below, above) = open.split_at(index);
+            let below = &below[index - 1];
+            let is_open = |row: usize, col: usize| {
+                below
+                    .get(row)
+                    .and_then(|cells| cells.get(col))
+                    .copied()
+                    .unwrap_or(false)
+            };
+            for (row, cells) in above[0].iter().enumerate() {
+                for (col, cell) in cells.iter().enumerate() {
+                    let supported = is_open(row, col)
+                        && is_open(row, col + 1)
+                        && is_open(row + 1, col)
+                        && is_open(row + 1, col + 1);
+                    if *cell && !supported {
+                        return Result::Err(invalid(
+                            lines[index][row],
+                            col + 1,
+                            format!(
+                                "The open cell does not rest on 4 open cells of layer {}",
+                                index
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let mask = BoardMask {
+            name: name.to_string(),
+            open,
+            masks: OnceCell::new(),
+        };
+        check_open_cell_count(mask.open_cell_count())?;
+        Result::Ok(mask)
+    }
+
+    /// The name the board was read with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.open.len()
+    }
+
+    /// The number of cells that can be filled.
+    pub fn open_cell_count(&self) -> usize {
+        self.open
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|open| **open)
+            .count()
+    }
+
+    /// The layers of an empty board in this shape. Blocked cells hold `BLOCKED_SLOT`, which is
+    /// never empty, so pieces never fit in them.
+    pub(crate) fn empty_layers(&self) -> GridLayers<char> {
+        let dimensions: Vec<(usize, usize)> = self
+            .open
+            .iter()
+            .map(|layer| (layer.len(), layer.first().map_or(0, |row| row.len())))
+            .collect();
+        let mut layers = GridLayers::new(&dimensions, EMPTY_SLOT);
+        for (layer, rows) in self.open.iter().enumerate() {
+            for (row, cells) in rows.iter().enumerate() {
+                for (col, open) in cells.iter().enumerate() {
+                    if !open {
+                        layers.update(layer, row, col, BLOCKED_SLOT);
+                    }
+                }
+            }
+        }
+        layers
+    }
+
+    /// The masks shared by every board with this shape. The board is filled with the Kanoodle
+    /// pieces (other sets have their own masks, see `BoardType::with_pieces`).
+    pub(crate) fn shape_masks(&self) -> Arc<ShapeMasks> {
+        Arc::clone(self.masks.get_or_init(|| {
+            Arc::new(
+                ShapeMasks::new(&self.empty_layers(), PieceSet::kanoodle())
+                    .expect("The open cells were counted when the board was read"),
+            )
+        }))
+    }
+}
+
+/// Boards with the same name and cells are the same board type.
+impl PartialEq for BoardMask {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.open == other.open
+    }
+}
+
+/// Only the name is shown, so the board type displays as `Custom(tray.txt)`.
+impl Debug for BoardMask {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
@@ -0,0 +1,230 @@
+use crate::board::BoardType;
+use crate::error::Error;
+use crate::layer::Position;
+use crate::placements::RequestedPiece;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A snapshot of a search that can be saved to a file and resumed later. (See
+/// `Solver::checkpoint` and `Solver::resume`)
+///
+/// The placements path and the board describe exactly where the search is. The next piece to try
+/// and the piece the search may not backtrack beyond describe where it goes from there. It is
+/// saved as plain text:
+///
+/// ```text
+/// kanoodle-solver checkpoint
+/// board-type: Rectangle
+/// path: F[02]; H[02]; J[00]; D[00]; C[07]; A[07]; I[00];
+/// next: B[02]
+/// next-position: 0 1 8
+/// unreported-solution: false
+/// fence: J
+/// count solutions: 29
+/// initial:
+/// HHJJJJ.....
+/// FHH........
+/// FFH........
+/// ...........
+/// ...........
+/// board:
+/// HHJJJJDDDDC
+/// FHHAIIID..C
+/// FFHAI.I...C
+/// ..AA.....CC
+/// ...........
+/// ```
+///
+/// The initial state and the board use the same format as an initial state sent to stdin. Empty
+/// lines separate the layers of the pyramid.
+pub struct Checkpoint {
+    pub(crate) board_type: BoardType,
+    pub(crate) initial_state: Vec<String>,
+    pub(crate) state: Vec<String>,
+    pub(crate) path: Vec<RequestedPiece>,
+    pub(crate) next: Option<RequestedPiece>,
+    /// The position the next piece is tried in (layer, row and column).
+    pub(crate) next_position: Position,
+    pub(crate) unreported_solution: bool,
+    pub(crate) fence: Option<char>,
+    /// Counts kept by the caller, such as the number of solutions found so far. They are saved
+    /// and restored as they are.
+    pub counts: BTreeMap<String, u64>,
+}
+
+const HEADER: &str = "kanoodle-solver checkpoint";
+
+impl Checkpoint {
+    pub fn board_type(&self) -> &BoardType {
+        &self.board_type
+    }
+}
+
+impl Display for Checkpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "board-type: {:?}", self.board_type)?;
+        write!(f, "path:")?;
+        for piece in &self.path {
+            write!(f, " {};", piece)?;
+        }
+        writeln!(f)?;
+        match &self.next {
+            Option::Some(piece) => writeln!(f, "next: {}", piece)?,
+            Option::None => writeln!(f, "next: none")?,
+        }
+        let Position(layer, row, col) = self.next_position;
+        writeln!(f, "next-position: {} {} {}", layer, row, col)?;
+        writeln!(f, "unreported-solution: {}", self.unreported_solution)?;
+        match self.fence {
+            Option::Some(name) => writeln!(f, "fence: {}", name)?,
+            Option::None => writeln!(f, "fence: none")?,
+        }
+        for (name, count) in &self.counts {
+            writeln!(f, "count {}: {}", name, count)?;
+        }
+        writeln!(f, "initial:")?;
+        writeln!(f, "{}", self.initial_state.join("\n\n"))?;
+        writeln!(f, "board:")?;
+        writeln!(f, "{}", self.state.join("\n\n"))
+    }
+}
+
+impl FromStr for Checkpoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s).map_err(Error::InvalidCheckpoint)
+    }
+}
+
+fn parse(s: &str) -> Result<Checkpoint, String> {
+    let mut lines = s.lines();
+    if lines.next().map(|line| line.trim_end()) != Option::Some(HEADER) {
+        return Result::Err("Not a checkpoint file".to_string());
+    }
+
+    let mut board_type = Option::None;
+    let mut path = Option::None;
+    let mut next = Option::None;
+    let mut next_position = Option::None;
+    let mut unreported_solution = Option::None;
+    let mut fence = Option::None;
+    let mut counts = BTreeMap::new();
+    let mut initial_state = Option::None;
+    let mut state = Option::None;
+
+    // The grids are the only values that span multiple lines, so they are read into
+    // `grid` until the next grid starts.
+    let mut grid: Option<(&str, Vec<&str>)> = Option::None;
+    for line in lines {
+        let line = line.trim_end();
+        if line == "initial:" || line == "board:" {
+            finish_grid(grid.take(), &mut initial_state, &mut state);
+            grid = Option::Some((line, Vec::new()));
+            continue;
+        }
+        if let Option::Some((_, rows)) = &mut grid {
+            rows.push(line);
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once(':')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or(format!("Invalid checkpoint line: {}", line))?;
+        match key {
+            "board-type" => board_type = Option::Some(value.parse::<BoardType>()?),
+            "path" => {
+                path = Option::Some(
+                    value
+                        .split(';')
+                        .map(|piece| piece.trim())
+                        .filter(|piece| !piece.is_empty())
+                        .map(|piece| piece.parse::<RequestedPiece>())
+                        .collect::<Result<Vec<RequestedPiece>, Error>>()
+                        .map_err(|e| e.to_string())?,
+                )
+            }
+            "next" => {
+                next = Option::Some(match value {
+                    "none" => Option::None,
+                    piece => {
+                        Option::Some(piece.parse::<RequestedPiece>().map_err(|e| e.to_string())?)
+                    }
+                })
+            }
+            "next-position" => {
+                let coordinates = value
+                    .split_whitespace()
+                    .map(|coordinate| coordinate.parse::<usize>())
+                    .collect::<Result<Vec<usize>, _>>()
+                    .map_err(|_| format!("Invalid next-position: {}", value))?;
+                match coordinates.as_slice() {
+                    [layer, row, col] => next_position = Option::Some(Position(*layer, *row, *col)),
+                    _ => return Result::Err(format!("Invalid next-position: {}", value)),
+                }
+            }
+            "unreported-solution" => {
+                unreported_solution = Option::Some(
+                    value
+                        .parse::<bool>()
+                        .map_err(|_| format!("Invalid unreported-solution: {}", value))?,
+                )
+            }
+            "fence" => {
+                fence = Option::Some(match value {
+                    "none" => Option::None,
+                    name => Option::Some(
+                        name.chars()
+                            .next()
+                            .ok_or(format!("Invalid fence: {}", value))?,
+                    ),
+                })
+            }
+            count if count.starts_with("count ") => {
+                let count_value = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid count: {}", line))?;
+                counts.insert(count["count ".len()..].to_string(), count_value);
+            }
+            _ => return Result::Err(format!("Invalid checkpoint line: {}", line)),
+        }
+    }
+    finish_grid(grid.take(), &mut initial_state, &mut state);
+
+    let missing = |name: &str| format!("The checkpoint is missing {}", name);
+    Result::Ok(Checkpoint {
+        board_type: board_type.ok_or_else(|| missing("board-type"))?,
+        initial_state: initial_state.ok_or_else(|| missing("initial"))?,
+        state: state.ok_or_else(|| missing("board"))?,
+        path: path.ok_or_else(|| missing("path"))?,
+        next: next.ok_or_else(|| missing("next"))?,
+        next_position: next_position.ok_or_else(|| missing("next-position"))?,
+        unreported_solution: unreported_solution.ok_or_else(|| missing("unreported-solution"))?,
+        fence: fence.ok_or_else(|| missing("fence"))?,
+        counts,
+    })
+}
+
+/// Splits the rows of a grid into layers and stores them as the initial state or the board.
+fn finish_grid(
+    grid: Option<(&str, Vec<&str>)>,
+    initial_state: &mut Option<Vec<String>>,
+    state: &mut Option<Vec<String>>,
+) {
+    if let Option::Some((name, rows)) = grid {
+        let layers: Vec<String> = rows
+            .join("\n")
+            .trim_end()
+            .split("\n\n")
+            .map(|layer| layer.to_string())
+            .collect();
+        if name == "initial:" {
+            *initial_state = Option::Some(layers);
+        } else {
+            *state = Option::Some(layers);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::pieces::PieceSet;
+use std::str::FromStr;
+
+/// When boards and pieces are displayed in color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+    /// Only when writing to a terminal.
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            Result::Ok(ColorMode::Auto)
+        } else if s.eq_ignore_ascii_case("always") {
+            Result::Ok(ColorMode::Always)
+        } else if s.eq_ignore_ascii_case("never") {
+            Result::Ok(ColorMode::Never)
+        } else {
+            Result::Err(format!(
+                "Unknown color mode {} (expected auto, always or never)",
+                s
+            ))
+        }
+    }
+}
+
+/// The colors a piece is drawn in. Every piece always has the same colors (in SVG images and on
+/// the terminal), so solutions can be compared at a glance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PieceColor {
+    /// The color as an RGB hex string (`#rrggbb`).
+    pub rgb: &'static str,
+    /// The closest color in the 256 color palette of ANSI terminals.
+    pub ansi: u8,
+    /// Set if the color is dark enough that text on top of it has to be white.
+    pub dark: bool,
+}
+
+const fn color(rgb: &'static str, ansi: u8, dark: bool) -> PieceColor {
+    PieceColor { rgb, ansi, dark }
+}
+
+/// The colors of every piece, in piece name order (`A` - `L`). Piece sets with more than 12
+/// pieces use the colors again, starting from the first one.
+const PALETTE: [PieceColor; 12] = [
+    color("#e6194b", 197, true),
+    color("#f58231", 208, false),
+    color("#ffe119", 220, false),
+    color("#3cb44b", 71, false),
+    color("#42d4f4", 45, false),
+    color("#4363d8", 26, true),
+    color("#911eb4", 91, true),
+    color("#f032e6", 201, false),
+    color("#a9a9a9", 248, false),
+    color("#9a6324", 130, true),
+    color("#800000", 88, true),
+    color("#469990", 30, true),
+];
+
+/// The color used for letters that aren't piece names.
+const UNKNOWN: PieceColor = color("#dddddd", 253, false);
+
+/// The color of the piece with the letter. Pieces are colored in name order, so the same set
+/// always has the same colors.
+pub fn piece_color(letter: char, pieces: &PieceSet) -> PieceColor {
+    match pieces.names().iter().position(|name| *name == letter) {
+        Option::Some(index) => PALETTE[index % PALETTE.len()],
+        Option::None => UNKNOWN,
+    }
+}
+
+/// Wraps text in the ANSI escape codes that give it the piece's color as its background.
+pub fn ansi_cell(letter: char, pieces: &PieceSet, text: &str) -> String {
+    let color = piece_color(letter, pieces);
+    format!(
+        "\x1b[{};48;5;{}m{}\x1b[0m",
+        if color.dark { 97 } else { 30 },
+        color.ansi,
+        text
+    )
+}
+
+/// Displays a board (see `Board::colored`) or a shape (see `Shape::colored`) with a background
+/// color per piece.
+pub struct Colored<'a, T> {
+    pub(crate) value: &'a T,
+    /// The letter of the piece when displaying a shape. (Boards already know which piece is in
+    /// each cell).
+    pub(crate) letter: char,
+    /// The pieces the letters are colored by.
+    pub(crate) pieces: &'a PieceSet,
+}
@@ -0,0 +1,403 @@
+use crate::board::{Board, BoardType};
+use crate::error::Error;
+use crate::layer::Position;
+use crate::placements::RequestedPiece;
+use crate::solver::{initialize, Solution};
+use crate::symmetry::Symmetry;
+use crate::Placements;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Finds solutions using Knuth's Algorithm X with dancing links (DLX).
+///
+/// Finding a solution is treated as an exact cover problem. There is a column for every empty
+/// cell on the board and for every piece that has not been placed yet. Every orientation of every
+/// remaining piece that fits somewhere on the board is a row that covers the piece's column and
+/// the columns of the cells it fills. A set of rows that covers every column exactly once is a
+/// solution.
+///
+/// Unlike the depth-first `Solver`, which always fills the top-most, left-most empty cell, the
+/// next column to cover is the one with the fewest candidate rows. That means solutions are
+/// **not** found in lexical order. The set of solutions (including their placement paths) is
+/// identical though, so the two engines can be used to cross-check each other.
+pub struct DlxSolver {
+    board: Board,
+    initial_path: Vec<RequestedPiece>,
+    rows: Vec<Placement>,
+    exact_cover: ExactCover,
+    symmetry: Option<Arc<Symmetry>>,
+}
+
+/// A piece in a specific orientation at a specific position on the board.
+struct Placement {
+    name: char,
+    orientation_index: usize,
+    cells: Vec<Position>,
+}
+
+impl DlxSolver {
+    /// Creates a solver for the requested board type. The pieces in the initial state (if there
+    /// is one) are never removed from the board. Fails for the same initial states as
+    /// `Solver::new`.
+    pub fn new(
+        board_type: &BoardType,
+        initial_state: Option<Vec<String>>,
+    ) -> Result<DlxSolver, Error> {
+        let (board, placements, _) = initialize(initial_state, board_type, false)?;
+        let initial_path = placements.path();
+        let allow_3d_orientations = board_type.allows_3d_orientations();
+
+        let mut columns = HashMap::new();
+
+        let empty_cells: Vec<Position> = board
+            .positions()
+            .into_iter()
+            .filter(|position| board.letter_at(position).is_none())
+            .collect();
+        for position in &empty_cells {
+            columns.insert(Column::Cell(*position), columns.len());
+        }
+
+        let pieces = board_type.pieces();
+        let remaining_pieces: Vec<char> = pieces
+            .names()
+            .iter()
+            .copied()
+            .filter(|name| !initial_path.iter().any(|placed| placed.name == *name))
+            .collect();
+        for name in &remaining_pieces {
+            columns.insert(Column::Piece(*name), columns.len());
+        }
+
+        let mut rows = Vec::new();
+        let mut row_columns = Vec::new();
+        for name in remaining_pieces {
+            for (orientation_index, shape) in pieces[&name].orientations.iter().enumerate() {
+                if shape.is_3d && !allow_3d_orientations {
+                    continue;
+                }
+
+                for position in &empty_cells {
+                    if let Result::Ok(cells) = board.fit_shape_at(shape, position) {
+                        let mut covered = vec![columns[&Column::Piece(name)]];
+                        covered.extend(cells.iter().map(|cell| columns[&Column::Cell(*cell)]));
+                        row_columns.push(covered);
+                        rows.push(Placement {
+                            name,
+                            orientation_index,
+                            cells,
+                        });
+                    }
+                }
+            }
+        }
+
+        Result::Ok(DlxSolver {
+            board,
+            initial_path,
+            rows,
+            exact_cover: ExactCover::new(columns.len(), &row_columns),
+            symmetry: Option::None,
+        })
+    }
+
+    /// Only finds the canonical solution of every class of symmetric solutions. The symmetry
+    /// must have been created from the initial board state. (See `Solver::with_symmetry`)
+    pub fn with_symmetry(mut self, symmetry: Arc<Symmetry>) -> DlxSolver {
+        let masks = self.board.masks();
+        let disallowed: Vec<bool> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mask = row
+                    .cells
+                    .iter()
+                    .fold(0u64, |mask, cell| mask | masks.bit_of(cell));
+                !symmetry.allows(row.name, mask)
+            })
+            .collect();
+        self.exact_cover.remove_rows(|row| disallowed[row]);
+
+        self.symmetry = Option::Some(symmetry);
+        self
+    }
+
+    /// The initial state of the board.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Turns a set of rows into the same solution the depth-first solver would have found.
+    ///
+    /// The depth-first solver always places a piece in the top-most, left-most empty cell, so
+    /// that's the order in which the pieces are added to the board and the placements path.
+    fn to_solution(&self, row_indexes: Vec<usize>) -> Solution {
+        let mut board = self.board.clone();
+        let pieces = Arc::clone(board.pieces());
+        let mut path = self.initial_path.clone();
+        let mut remaining: Vec<&Placement> = row_indexes.iter().map(|i| &self.rows[*i]).collect();
+
+        while !remaining.is_empty() {
+            let next_pos = *board.next_pos();
+            let index = remaining
+                .iter()
+                .position(|placement| placement.cells.contains(&next_pos))
+                .expect("Every empty cell is covered by an exact cover solution");
+            let placement = remaining.swap_remove(index);
+
+            let shape = &pieces[&placement.name].orientations[placement.orientation_index];
+            board
+                .try_add_shape(shape, placement.name)
+                .expect("Pieces in an exact cover solution never overlap");
+            path.push(RequestedPiece {
+                name: placement.name,
+                orientation_index: placement.orientation_index,
+            });
+        }
+
+        Solution {
+            placements: Placements::with_path(&self.board.board_type(), path)
+                .expect("An exact cover solution has every piece once"),
+            board,
+        }
+    }
+}
+
+impl Iterator for DlxSolver {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        loop {
+            let row_indexes = self.exact_cover.next()?;
+            let solution = self.to_solution(row_indexes);
+            if self
+                .symmetry
+                .as_ref()
+                .is_none_or(|symmetry| symmetry.is_canonical(&solution.board))
+            {
+                return Option::Some(solution);
+            }
+        }
+    }
+}
+
+#[derive(Hash, Eq, PartialEq)]
+enum Column {
+    Cell(Position),
+    Piece(char),
+}
+
+/// A sparse exact cover matrix stored as a toroidal doubly linked list, as described in Knuth's
+/// "Dancing Links" paper.
+///
+/// Node 0 is the root, nodes `1..=columns` are the column headers and the remaining nodes are the
+/// ones in the matrix. Links are indexes into the vectors below.
+///
+/// The search is iterative rather than recursive so that it can be paused after every solution.
+struct ExactCover {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    row: Vec<usize>,
+    /// The number of nodes in each column. Only meaningful for header nodes.
+    size: Vec<usize>,
+    /// The node (row) chosen at every level of the search.
+    chosen: Vec<usize>,
+    started: bool,
+}
+
+enum Step {
+    /// Cover the column with the fewest remaining rows with its first row.
+    Descend,
+    /// Undo the last chosen row and choose the next row in its column instead.
+    Advance,
+}
+
+impl ExactCover {
+    fn new(columns: usize, rows: &[Vec<usize>]) -> ExactCover {
+        let headers = columns + 1;
+        let mut matrix = ExactCover {
+            left: (0..headers).map(|i| (i + headers - 1) % headers).collect(),
+            right: (0..headers).map(|i| (i + 1) % headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            row: vec![usize::MAX; headers],
+            size: vec![0; headers],
+            chosen: Vec::new(),
+            started: false,
+        };
+
+        for (row_index, row_columns) in rows.iter().enumerate() {
+            let mut first: Option<usize> = Option::None;
+            for column_index in row_columns {
+                let header = column_index + 1;
+                let node = matrix.column.len();
+
+                // Insert at the bottom of the column.
+                matrix.column.push(header);
+                matrix.row.push(row_index);
+                matrix.up.push(matrix.up[header]);
+                matrix.down.push(header);
+                let above = matrix.up[header];
+                matrix.down[above] = node;
+                matrix.up[header] = node;
+                matrix.size[header] += 1;
+
+                // Insert at the end of the row.
+                match first {
+                    Option::None => {
+                        matrix.left.push(node);
+                        matrix.right.push(node);
+                        first = Option::Some(node);
+                    }
+                    Option::Some(first) => {
+                        let last = matrix.left[first];
+                        matrix.left.push(last);
+                        matrix.right.push(first);
+                        matrix.right[last] = node;
+                        matrix.left[first] = node;
+                    }
+                }
+            }
+        }
+
+        matrix
+    }
+
+    /// Removes every row the predicate matches from the matrix. This may only be done before the
+    /// search starts.
+    fn remove_rows(&mut self, remove: impl Fn(usize) -> bool) {
+        for node in self.size.len()..self.column.len() {
+            if remove(self.row[node]) {
+                self.down[self.up[node]] = self.down[node];
+                self.up[self.down[node]] = self.up[node];
+                self.size[self.column[node]] -= 1;
+            }
+        }
+    }
+
+    fn cover(&mut self, header: usize) {
+        self.right[self.left[header]] = self.right[header];
+        self.left[self.right[header]] = self.left[header];
+
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                self.up[self.down[j]] = self.up[j];
+                self.down[self.up[j]] = self.down[j];
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                self.up[self.down[j]] = j;
+                self.down[self.up[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+
+        self.right[self.left[header]] = header;
+        self.left[self.right[header]] = header;
+    }
+
+    /// Chooses a row. Every other column the row covers is removed from the matrix.
+    fn choose(&mut self, node: usize) {
+        self.chosen.push(node);
+        let mut j = self.right[node];
+        while j != node {
+            self.cover(self.column[j]);
+            j = self.right[j];
+        }
+    }
+
+    /// Reverts `choose` for the last chosen row and returns it.
+    fn unchoose(&mut self) -> Option<usize> {
+        let node = self.chosen.pop()?;
+        let mut j = self.left[node];
+        while j != node {
+            self.uncover(self.column[j]);
+            j = self.left[j];
+        }
+        Option::Some(node)
+    }
+
+    /// The column with the fewest rows left. Those are the hardest to cover, so trying them first
+    /// keeps the search tree small.
+    fn fewest_candidates_column(&self) -> usize {
+        let mut best = self.right[0];
+        let mut header = self.right[best];
+        while header != 0 {
+            if self.size[header] < self.size[best] {
+                best = header;
+            }
+            header = self.right[header];
+        }
+        best
+    }
+}
+
+impl Iterator for ExactCover {
+    /// The indexes of the rows that make up a solution.
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        // After a solution has been returned, we pick up the search by moving on from the last
+        // chosen row.
+        let mut step = if self.started {
+            Step::Advance
+        } else {
+            self.started = true;
+            Step::Descend
+        };
+
+        loop {
+            step = match step {
+                Step::Descend => {
+                    if self.right[0] == 0 {
+                        // Every column is covered.
+                        return Option::Some(
+                            self.chosen.iter().map(|node| self.row[*node]).collect(),
+                        );
+                    }
+
+                    let header = self.fewest_candidates_column();
+                    if self.size[header] == 0 {
+                        // The column cannot be covered, so this is a dead end.
+                        Step::Advance
+                    } else {
+                        self.cover(header);
+                        self.choose(self.down[header]);
+                        Step::Descend
+                    }
+                }
+                Step::Advance => {
+                    // Once there is nothing left to undo, every possibility has been tried.
+                    let node = self.unchoose()?;
+                    let header = self.column[node];
+                    let next = self.down[node];
+                    if next == header {
+                        // That was the last row in the column.
+                        self.uncover(header);
+                        Step::Advance
+                    } else {
+                        self.choose(next);
+                        Step::Descend
+                    }
+                }
+            }
+        }
+    }
+}
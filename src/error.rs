@@ -0,0 +1,127 @@
+use crate::layer::Position;
+use crate::placements::RequestedPiece;
+use std::fmt::{Display, Formatter};
+
+/// The ways setting up a search (or reading its input) can fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// There is no piece with the name.
+    UnknownPiece(char),
+    /// A piece and orientation that is not written like `A[03]`.
+    InvalidOrientationSyntax(String),
+    /// The piece doesn't have an orientation with the index.
+    UnknownOrientation(RequestedPiece),
+    /// The cells of a piece in a board state don't match any of the piece's orientations. The
+    /// shape is drawn the same way `Shape` displays.
+    OrientationNotFound { piece: char, shape: String },
+    /// A piece of a board state overlaps another piece or runs off the board.
+    PieceDoesNotFit { piece: char, position: Position },
+    /// The same piece was placed more than once.
+    DuplicatePiece(char),
+    /// A board state that doesn't fit on the board (such as too many layers or rows).
+    MalformedBoard(String),
+    /// A board state that can't be read (see `state::parse_state`). Lines and columns are
+    /// counted from 1.
+    InvalidState {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A checkpoint file that can't be read or doesn't match its own board.
+    InvalidCheckpoint(String),
+    /// A board file that can't be read (see `BoardMask::parse`). Lines and columns are counted
+    /// from 1.
+    InvalidBoardFile {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A piece file that can't be read (see `PieceSet::parse`). Lines and columns are counted
+    /// from 1.
+    InvalidPieceFile {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The pieces can't fill the board, since it doesn't have as many open cells as they have.
+    CellCountMismatch {
+        open_cells: usize,
+        piece_cells: usize,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownPiece(name) => {
+                write!(
+                    f,
+                    "Unknown piece {} (it is not one of the board's pieces)",
+                    name
+                )
+            }
+            Error::InvalidOrientationSyntax(s) => write!(
+                f,
+                "Invalid piece {} (expected a name and a two digit orientation, like A[03])",
+                s
+            ),
+            Error::UnknownOrientation(piece) => {
+                write!(
+                    f,
+                    "Piece {} does not have orientation {}",
+                    piece.name, piece
+                )
+            }
+            Error::OrientationNotFound { piece, shape } => write!(
+                f,
+                "The shape of piece {} is not one of its orientations:\n{}",
+                piece, shape
+            ),
+            Error::PieceDoesNotFit { piece, position } => write!(
+                f,
+                "Piece {} does not fit on the board at ({}, {}, {})",
+                piece, position.0, position.1, position.2
+            ),
+            Error::DuplicatePiece(name) => write!(f, "Piece {} is placed more than once", name),
+            Error::MalformedBoard(message) => write!(f, "Malformed board: {}", message),
+            Error::InvalidState {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Invalid board state at line {}, column {}: {}",
+                line, column, message
+            ),
+            Error::InvalidCheckpoint(message) => write!(f, "Invalid checkpoint: {}", message),
+            Error::InvalidBoardFile {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Invalid board file at line {}, column {}: {}",
+                line, column, message
+            ),
+            Error::InvalidPieceFile {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Invalid piece file at line {}, column {}: {}",
+                line, column, message
+            ),
+            Error::CellCountMismatch {
+                open_cells,
+                piece_cells,
+            } => write!(
+                f,
+                "The board has {} open cells, but the pieces have {} cells, so it can't be filled",
+                open_cells, piece_cells
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,96 @@
+use crate::board::{Board, BoardType};
+use crate::random::Random;
+use crate::solver::{Solution, Solver};
+
+/// Once this few pieces are left to place, the rest of a random solution is found with the
+/// normal depth-first search. Searching the last few pieces in a random order doesn't make the
+/// solutions much more varied, and it is faster to let the search backtrack on its own.
+const SEARCHED_PIECES: usize = 5;
+
+/// A board with some pieces already placed that can be completed in exactly one way, like the
+/// challenges in the Kanoodle booklet.
+pub struct Challenge {
+    /// The pieces that are given. (`Board::state` writes it in the format of an initial
+    /// state).
+    pub board: Board,
+    /// The pieces that were removed, in the order they were removed.
+    pub missing: Vec<char>,
+    /// The only way to complete the board.
+    pub solution: Solution,
+}
+
+/// Finds a random solution. The first pieces are picked at random out of every piece that fits
+/// in the next position (see `Solver::split`), so every seed leads to a different part of the
+/// search tree.
+///
+/// Returns `None` if the board has no solutions, which is only possible for boards read from a
+/// file or filled with a different piece set (including a set that can't fill the board at all).
+pub fn random_solution(board_type: &BoardType, random: &mut Random) -> Option<Solution> {
+    let solver = Solver::new(board_type, Option::None, false).ok()?;
+    random_descent(solver, random)
+}
+
+fn random_descent(mut solver: Solver, random: &mut Random) -> Option<Solution> {
+    if solver.initial_board().pieces().len() - solver.path().len() <= SEARCHED_PIECES {
+        return solver.next();
+    }
+
+    let mut subtrees = solver.split(1);
+    random.shuffle(&mut subtrees);
+    subtrees
+        .into_iter()
+        .find_map(|subtree| random_descent(subtree, random))
+}
+
+/// Generates a challenge with up to `missing` pieces missing from a random solution.
+///
+/// Pieces are removed one at a time, in a random order. After each removal the board is
+/// searched, and the piece is only left out if the board can still be completed in exactly one
+/// way. Removing more pieces usually leaves more than one way to complete the board, so there
+/// may be fewer missing pieces than requested.
+///
+/// Returns `None` if the board has no solutions (see `random_solution`).
+pub fn generate_challenge(
+    board_type: &BoardType,
+    missing: usize,
+    random: &mut Random,
+) -> Option<Challenge> {
+    let solution = random_solution(board_type, random)?;
+
+    let mut pieces: Vec<char> = solution
+        .placements
+        .path()
+        .iter()
+        .map(|piece| piece.name)
+        .collect();
+    random.shuffle(&mut pieces);
+
+    let mut board = solution.board.clone();
+    let mut removed = Vec::new();
+    for name in pieces {
+        if removed.len() == missing {
+            break;
+        }
+
+        let mut candidate = board.clone();
+        candidate.remove_shape(name);
+        if count_solutions(&candidate, 2) == 1 {
+            board = candidate;
+            removed.push(name);
+        }
+    }
+
+    Option::Some(Challenge {
+        board,
+        missing: removed,
+        solution,
+    })
+}
+
+/// Counts the ways the board can be completed, but stops counting at `limit`.
+fn count_solutions(board: &Board, limit: usize) -> usize {
+    Solver::new(&board.board_type(), Option::Some(board.state()), false)
+        .expect("The pieces of a solution are always valid")
+        .take(limit)
+        .count()
+}
@@ -1,3 +1,4 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Position(pub usize, pub usize, pub usize);
 
 pub trait Layers<T: Copy + PartialEq> {
@@ -27,92 +28,77 @@ pub trait Layers<T: Copy + PartialEq> {
     }
 }
 
-#[macro_export]
-macro_rules! count {
-    ($t:tt) => { 1usize };
-    ($t:tt, $($rest:tt),*) => {
-        1usize + count!($({ $rest }),*)
-    }
+/// Layers whose number and dimensions are chosen at run time. The cells of every layer are kept
+/// one after the other in a single `Vec`, row by row.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct GridLayers<T> {
+    cells: Vec<T>,
+    /// The number of rows and columns in each layer, and the index of its first cell in `cells`.
+    layers: Vec<LayerDimensions>,
+    default: T,
 }
 
-#[macro_export]
-macro_rules! match_layer {
-    ($self:ident, $index:ident, mut $layer:ident, $row:ident, $col:ident, $val:ident, $($i:tt),+ $block:block) => {
-        match $index {
-            $(
-                $i => {
-                    let $layer = &mut $self.$i;
-                    $block
-                },
-            )+
-            _ => panic!("layer not found!")
-        }
-    };
-    ($self:ident, $index:ident, $layer:ident, $row:ident, $col:ident, $($i:tt),+ $block:block) => {
-        match $index {
-            $(
-                $i => {
-                    let $layer = &$self.$i;
-                    $block
-                },
-            )+
-            _ => panic!("layer not found!")
-        }
-    }
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+struct LayerDimensions {
+    rows: usize,
+    cols: usize,
+    offset: usize,
 }
 
-#[macro_export]
-macro_rules! define_layers {
-    ($struct:ident, $type:ty, $default:expr, $({ layer: $layer:tt, rows: $rows:literal, cols: $cols:literal }),+) => {
-        #[derive(Clone, Debug, Hash, Eq, PartialEq)]
-        pub struct $struct($([[$type; $cols]; $rows],)+);
-
-        impl $struct {
-            const fn layer_count() -> usize {
-                count!($($rows),+)
-            }
-
-            const fn default_cell_value() -> $type {
-                $default
-            }
+impl<T: Copy + PartialEq> GridLayers<T> {
+    /// Creates layers with the specified number of rows and columns, from the bottom layer to the
+    /// top one. Every cell starts with the default value.
+    pub fn new(dimensions: &[(usize, usize)], default: T) -> GridLayers<T> {
+        let mut layers = Vec::with_capacity(dimensions.len());
+        let mut cell_count = 0;
+        for (rows, cols) in dimensions {
+            layers.push(LayerDimensions {
+                rows: *rows,
+                cols: *cols,
+                offset: cell_count,
+            });
+            cell_count += rows * cols;
         }
+        GridLayers {
+            cells: vec![default; cell_count],
+            layers,
+            default,
+        }
+    }
 
-        impl crate::layer::Layers<$type> for $struct {
-
-            fn default_cell_value(&self) -> $type {
-                $struct::default_cell_value()
-            }
+    fn index(&self, layer_index: usize, row_index: usize, col_index: usize) -> usize {
+        let layer = &self.layers[layer_index];
+        debug_assert!(
+            row_index < layer.rows && col_index < layer.cols,
+            "cell ({}, {}) is not in layer {}",
+            row_index,
+            col_index,
+            layer_index
+        );
+        layer.offset + row_index * layer.cols + col_index
+    }
+}
 
-            fn layer_count(&self) -> usize {
-                $struct::layer_count()
-            }
+impl<T: Copy + PartialEq> Layers<T> for GridLayers<T> {
+    fn default_cell_value(&self) -> T {
+        self.default
+    }
 
-            fn dimensions(&self, layer_index: usize) -> (usize, usize) {
-                match_layer!(self, layer_index, layer, r, c, $($layer),+ {
-                    // All rows in the layer have the same number of columns
-                    // So we can get the column count by checking the first
-                    // row's length.
-                    return (layer.len(), layer[0].len());
-                });
-            }
+    fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
 
-             fn at(&self, layer_index: usize, row_index: usize, col_index: usize) -> &$type {
-                match_layer!(self, layer_index, layer, row_index, col_index, $($layer),+ {
-                    return &layer[row_index][col_index];
-                });
-            }
+    fn dimensions(&self, layer_index: usize) -> (usize, usize) {
+        let layer = &self.layers[layer_index];
+        (layer.rows, layer.cols)
+    }
 
-            fn update(&mut self, layer_index: usize, row_index: usize, col_index: usize, val: $type) {
-                match_layer! (self, layer_index, mut layer, row_index, col_index, val, $($layer),+ {
-                    layer[row_index][col_index] = val;
-                });
-            }
-        }
+    fn at(&self, layer_index: usize, row_index: usize, col_index: usize) -> &T {
+        &self.cells[self.index(layer_index, row_index, col_index)]
+    }
 
-        impl Default for $struct {
-            fn default() -> Self {
-                $struct($([[$default; $cols]; $rows]),+ )
-            }
-        }
-    };
+    fn update(&mut self, layer_index: usize, row_index: usize, col_index: usize, val: T) {
+        let index = self.index(layer_index, row_index, col_index);
+        self.cells[index] = val;
+    }
 }
@@ -0,0 +1,44 @@
+//! Finds [Kanoodle](https://www.educationalinsights.com/kanoodle) solutions.
+//!
+//! The `Solver` is the main entry point. It is built from a board type, an optional
+//! initial board state and an optional ending path and hands back the solutions it
+//! finds as values.
+
+pub mod bitboard;
+pub mod board;
+pub mod board_file;
+pub mod checkpoint;
+pub mod color;
+pub mod dlx;
+pub mod error;
+pub mod generate;
+pub mod layer;
+pub mod output;
+pub mod parallel;
+pub mod pieces;
+pub mod placements;
+pub mod progress;
+pub mod random;
+pub mod rate;
+pub mod solver;
+pub mod state;
+pub mod svg;
+pub mod symmetry;
+
+pub use board::{Board, BoardType, CustomPieces};
+pub use board_file::BoardMask;
+pub use checkpoint::Checkpoint;
+pub use color::ColorMode;
+pub use dlx::DlxSolver;
+pub use error::Error;
+pub use generate::Challenge;
+pub use layer::{GridLayers, Layers, Position};
+pub use output::OutputFormat;
+pub use parallel::ParallelSolver;
+pub use pieces::{Piece, PieceSet, Shape};
+pub use placements::{PieceSuggestion, Placements, RequestedPiece, SearchOrder};
+pub use progress::ProgressEstimate;
+pub use random::Random;
+pub use rate::{Difficulty, Rating};
+pub use solver::{SearchProfile, SearchStats, SearchStep, Solution, Solver};
+pub use symmetry::{Symmetry, SymmetryMode};
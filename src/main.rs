@@ -1,17 +1,22 @@
+use std::collections::BTreeMap;
 use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use structopt::StructOpt;
 
-use crate::board::BoardType;
-use crate::layer::Layers;
-use crate::pieces::PIECES;
-use crate::placements::{PieceSuggestion, Placements, RequestedPiece};
-
-#[macro_use]
-mod layer;
-mod board;
-mod pieces;
-mod placements;
-mod solver;
+use kanoodle_solver::generate::generate_challenge;
+use kanoodle_solver::output::json_string;
+use kanoodle_solver::rate::rate;
+use kanoodle_solver::solver::{check_pieces, EndingAt, Engine};
+use kanoodle_solver::state::{parse_state, parse_state_leniently};
+use kanoodle_solver::svg::stacked_svg;
+use kanoodle_solver::{
+    Board, BoardMask, BoardType, Checkpoint, ColorMode, DlxSolver, Error, OutputFormat,
+    ParallelSolver, PieceSet, ProgressEstimate, Random, RequestedPiece, SearchOrder, SearchStats,
+    SearchStep, Solution, Solver, Symmetry, SymmetryMode,
+};
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -29,7 +34,7 @@ struct CliOptions {
     /// backtracking is not allowed).
     ///
     /// Example: --ending-at "B[00]" "C[01]"
-    #[structopt(short, long)]
+    #[structopt(short, long, global = true)]
     ending_at: Option<Vec<RequestedPiece>>,
 
     /// One or more pieces and orientations to display. When this option is present,
@@ -39,13 +44,33 @@ struct CliOptions {
     #[structopt(short = "p", long)]
     display_pieces: Option<Vec<RequestedPiece>>,
 
-    /// Indicates which type of board (Rectangle or Pyramid) should be used
-    /// when finding solutions.
+    /// Indicates which type of board (Rectangle, Pyramid, Pentomino-6x10,
+    /// Pentomino-5x12, Pentomino-4x15 or Pentomino-3x20) should be used when
+    /// finding solutions. The pentomino boards are filled with the 12 pentominoes
+    /// instead of the Kanoodle pieces.
     ///
     /// Defaults to "rectangular"
-    #[structopt(short = "t", long)]
+    #[structopt(short = "t", long, global = true)]
     board_type: Option<BoardType>,
 
+    /// Reads the shape of the board from this file instead of using one of the
+    /// board types. # is a blocked cell and . is an open cell. New lines separate
+    /// rows and empty lines separate layers, from the bottom layer to the top one.
+    /// The board must have as many open cells as the pieces have.
+    ///
+    /// May not be combined with --board-type, --checkpoint or --resume.
+    #[structopt(long, global = true, parse(from_os_str))]
+    board_file: Option<PathBuf>,
+
+    /// Reads the pieces from this file instead of using the 12 Kanoodle pieces.
+    /// Every piece is drawn with its name in its default orientation and . is an
+    /// empty cell. Any letter or symbol can be the name of a piece. The board must
+    /// have as many open cells as the pieces have.
+    ///
+    /// May not be combined with --checkpoint or --resume.
+    #[structopt(long, global = true, parse(from_os_str))]
+    pieces: Option<PathBuf>,
+
     /// If an initial state is specified, enabling this flag lets
     /// the solver remove pieces from the initial state once it has
     /// exhausted all possible solutions given the initial state.
@@ -53,57 +78,1281 @@ struct CliOptions {
     /// This flag has no effect when no initial state is passed.
     ///
     /// Defaults to false
-    #[structopt(short, long)]
+    #[structopt(short, long, global = true)]
     allow_backtracking: Option<bool>,
+
+    /// Searches for solutions on this many threads. The search tree is split into
+    /// subtrees (see --split-depth) which are searched in parallel. Solutions are
+    /// still reported in the same order as a single threaded search.
+    ///
+    /// May not be combined with --allow-backtracking.
+    ///
+    /// Defaults to a single threaded search
+    #[structopt(short = "j", long, global = true)]
+    threads: Option<usize>,
+
+    /// The number of pieces placed beyond the initial state to split the search tree
+    /// into subtrees when searching on multiple threads. Larger depths produce more
+    /// (and smaller) subtrees, which spreads the work more evenly between threads.
+    ///
+    /// Defaults to 2
+    #[structopt(long, global = true)]
+    split_depth: Option<usize>,
+
+    /// The search engine (dfs or dlx) used to find solutions.
+    ///
+    /// dfs is a depth-first search that finds solutions in lexical order. dlx solves
+    /// an exact cover problem with dancing links. It finds the same solutions, but
+    /// in a different order, so --ending-at only filters the solutions it reports.
+    /// dlx may not be combined with --threads or --allow-backtracking.
+    ///
+    /// Defaults to dfs
+    #[structopt(long, global = true)]
+    engine: Option<Engine>,
+
+    /// Takes advantage of the symmetry of the board (reduce or expand). Mirroring
+    /// or rotating a solution results in another solution, so only the canonical
+    /// solution of every class of symmetric solutions is searched for.
+    ///
+    /// reduce only reports the canonical solutions. expand also reports the
+    /// solutions derived from them by mirroring and rotating the board. Either way,
+    /// the number of solutions including and excluding symmetric ones is reported.
+    ///
+    /// Only symmetries that leave the initial state as it is apply. May not be
+    /// combined with --ending-at or --allow-backtracking.
+    ///
+    /// Defaults to reporting every solution without taking symmetry into account
+    #[structopt(long, global = true)]
+    symmetry: Option<SymmetryMode>,
+
+    /// Removes a piece as soon as it leaves a dead region behind: a group of empty
+    /// cells that no combination of the remaining pieces can fill (such as a single
+    /// empty cell). The same solutions are found either way, pruning just skips
+    /// searching boards that can never be solved.
+    ///
+    /// Only applies to the dfs engine.
+    ///
+    /// Defaults to true
+    #[structopt(long, global = true)]
+    prune_dead_regions: Option<bool>,
+
+    /// Reports how many pieces the search tried to place (nodes) and how many
+    /// nodes dead region pruning saved after the solutions. The saved nodes are
+    /// counted by searching below every pruned piece without pruning, so the
+    /// search takes about as long as a search without pruning.
+    ///
+    /// Only applies to the dfs engine.
+    #[structopt(long, global = true)]
+    stats: bool,
+
+    /// Saves the position of the search and the number of solutions found so far to
+    /// this file every --checkpoint-interval seconds, when the search is interrupted
+    /// (Ctrl+C) and when it finishes. Continue the search with --resume.
+    ///
+    /// Only applies to the dfs engine and may not be combined with --threads.
+    #[structopt(long, global = true, parse(from_os_str))]
+    checkpoint: Option<PathBuf>,
+
+    /// The number of seconds between saving checkpoints.
+    ///
+    /// Defaults to 60
+    #[structopt(long, global = true)]
+    checkpoint_interval: Option<u64>,
+
+    /// Continues the search saved in this checkpoint file. The board type and the
+    /// initial state are read from the file, so stdin is ignored. Other options
+    /// (such as --symmetry) are not saved and must be passed again. Solutions that
+    /// were found before the checkpoint was saved are counted, but not displayed
+    /// again.
+    ///
+    /// The search keeps saving checkpoints to the same file, unless --checkpoint
+    /// names a different one. Only applies to the dfs engine and may not be
+    /// combined with --threads.
+    #[structopt(long, global = true, parse(from_os_str))]
+    resume: Option<PathBuf>,
+
+    /// Shows the progress of the search on stderr: the estimated fraction of the
+    /// search that is done, the number of nodes (pieces tried) per second, the
+    /// number of solutions so far and the first pieces of the current path. The
+    /// estimate assumes every piece that fits beyond the initial state takes the
+    /// same time to search, so it is rough, but it keeps moving unless the search is
+    /// stuck.
+    ///
+    /// The line is updated every second on a terminal. Otherwise a line is written
+    /// every 10 seconds. Only applies to the dfs engine and may not be combined with
+    /// --threads.
+    #[structopt(long, global = true)]
+    progress: bool,
+
+    /// The format (text, json, ndjson or svg) solutions are written in. json writes
+    /// an array of records and ndjson writes one record per line. Every solution
+    /// record has the board type, the placements path, the orientation and cells
+    /// (layer, row and column) of every piece and the grid of every layer. The last
+    /// record is a summary with the number of solutions and the elapsed time.
+    ///
+    /// svg draws every solution in a single image, one below the other, with its
+    /// placements path above it. The layers of the pyramid are drawn side by side.
+    /// It may not be combined with the count command.
+    ///
+    /// Defaults to text
+    #[structopt(long, global = true)]
+    format: Option<OutputFormat>,
+
+    /// When boards and pieces are displayed with a background color per piece
+    /// (auto, always or never). auto only uses colors when writing to a terminal.
+    /// Only applies to the text format and --display-pieces.
+    ///
+    /// Defaults to auto
+    #[structopt(long, global = true)]
+    color: Option<ColorMode>,
+
+    /// Reads the initial state the forgiving way: any character that is not a
+    /// piece name (including other letters and symbols) is an empty cell, and
+    /// the layers, rows and columns are not checked against the board. Without
+    /// it, a state that doesn't fit the board, or has characters other than
+    /// piece names, ., spaces and # (on blocked cells), is rejected with the
+    /// line and column of the problem.
+    #[structopt(long, global = true)]
+    lenient: bool,
+
+    /// The seed of the random numbers used by generate and --random. The same
+    /// seed (and options) always generate the same challenge, or find the same
+    /// solutions in the same order.
+    ///
+    /// Defaults to a seed based on the current time, which is reported
+    #[structopt(long, global = true)]
+    seed: Option<u64>,
+
+    /// Tries the pieces, and the orientations of every piece, in a random order
+    /// (see --seed) instead of the lexical order. The same solutions are found, but
+    /// in a different order, so the first solution is different for every seed.
+    /// Combine it with --limit 1 to find a single random solution.
+    ///
+    /// Only applies to the dfs engine and may not be combined with --ending-at,
+    /// --symmetry, --checkpoint, --resume or --progress, since they rely on the
+    /// lexical order.
+    #[structopt(long, global = true)]
+    random: bool,
+
+    /// Stops the search after reporting this many solutions.
+    ///
+    /// Defaults to no limit
+    #[structopt(long, global = true)]
+    limit: Option<u64>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Counts the solutions without displaying them. The total is broken down by
+    /// the first piece (and orientation) placed beyond the initial state.
+    Count,
+    /// Checks a board state (such as a puzzle entered by hand) without solving it.
+    /// Every piece is checked for a valid orientation and for being used more than
+    /// once. A full board is checked for being a solution, and a partial board is
+    /// checked for having at least one solution.
+    ///
+    /// Exits with status 1 if a piece is invalid or the board can't be completed.
+    Validate,
+    /// Suggests a single piece for a partial board, without giving away the rest of
+    /// the solution. The piece covers the top-most, left-most empty cell and comes
+    /// from a solution that completes the board.
+    ///
+    /// Exits with status 1 if the board can't be completed.
+    Hint {
+        /// Keeps the board with every hint given so far in this file, so every
+        /// hint reveals one more piece. The board is read from stdin the first
+        /// time (when the file doesn't exist yet) and from the file after that.
+        #[structopt(long, parse(from_os_str))]
+        progressive: Option<PathBuf>,
+    },
+    /// Generates a challenge: a board with some pieces already placed that can be
+    /// completed in exactly one way. Pieces are removed from a random solution one at
+    /// a time, as long as the board still has a single solution.
+    ///
+    /// The board is written in the same format as an initial state, so it can be
+    /// sent to the solver. Everything else is written to stderr.
+    Generate {
+        /// The number of pieces to remove. Sometimes fewer can be removed, since the
+        /// board must still have a single solution.
+        #[structopt(long, default_value = "5")]
+        missing: usize,
+    },
+    /// Rates how difficult a board state is to complete by hand (Starter, Junior,
+    /// Expert or Master). Every solution is searched for, and the score combines the
+    /// branching factor at every depth, the number of dead ends (pieces that fit but
+    /// don't lead to a solution), the number of pieces tried before the first
+    /// solution and the number of solutions. The statistics are shown with it.
+    ///
+    /// Meant for challenges with a few pieces missing: searching a board with many
+    /// missing pieces takes as long as finding all of its solutions. Exits with
+    /// status 1 if the board can't be completed.
+    Rate,
+}
+
+/// A search set up from the command line options.
+struct Search {
+    initial_board: Board,
+    solutions: Solutions,
+    ending_at: Option<EndingAt>,
+    symmetry: Option<(Arc<Symmetry>, SymmetryMode)>,
+    stats: Option<Arc<SearchStats>>,
+    /// The totals so far. When resuming a search, they start at the totals saved in the
+    /// checkpoint.
+    totals: Totals,
+    /// The number of solutions left to report before the search stops (see --limit).
+    remaining: Option<u64>,
+    format: OutputFormat,
+    started: Instant,
+}
+
+enum Solutions {
+    /// The solutions to report. Solutions beyond the ending path are already left out.
+    All(Box<dyn Iterator<Item = Solution>>),
+    /// A depth-first search that is searched a step at a time, so checkpoints can be saved and
+    /// progress can be shown in between.
+    Stepped(Box<SteppedSearch>),
+}
+
+struct SteppedSearch {
+    solver: Solver,
+    checkpointing: Option<Checkpointing>,
+    progress: Option<Progress>,
+}
+
+struct Checkpointing {
+    file: PathBuf,
+    interval: Duration,
+    saved_at: Instant,
+    /// Set when the search is interrupted (Ctrl+C).
+    interrupted: Arc<AtomicBool>,
+}
+
+struct Progress {
+    estimate: ProgressEstimate,
+    stats: Arc<SearchStats>,
+    /// The progress line is redrawn in place on a terminal. Otherwise (such as when stderr is
+    /// redirected to a log file) a new line is written every time.
+    on_terminal: bool,
+    /// Set while the progress line is on the terminal.
+    line_shown: bool,
+    interval: Duration,
+    shown_at: Instant,
+    nodes_when_shown: u64,
+}
+
+/// The number of pieces the solver tries before checking whether a checkpoint is due (or the
+/// progress should be shown). It takes a small fraction of a second to try this many.
+const NODES_PER_STEP: u64 = 100_000;
+
+/// The number of pieces beyond the initial state shown in the progress line.
+const PROGRESS_PATH_PIECES: usize = 3;
+
+#[derive(Default)]
+struct Totals {
+    /// The number of solutions that were reported.
+    solutions: u64,
+    /// The number of solutions, excluding symmetric ones. Only counted when taking advantage
+    /// of symmetry.
+    distinct: u64,
+    /// The number of solutions, including symmetric ones (whether they were reported or not).
+    /// Only counted when taking advantage of symmetry.
+    raw: u64,
+    /// The number of solutions by the first piece placed beyond the initial state. Only
+    /// counted by the count command.
+    by_first_piece: BTreeMap<String, u64>,
+}
+
+impl Totals {
+    /// The totals as they are saved in a checkpoint.
+    fn to_counts(&self) -> BTreeMap<String, u64> {
+        let mut counts = BTreeMap::new();
+        counts.insert("solutions".to_string(), self.solutions);
+        counts.insert("distinct".to_string(), self.distinct);
+        counts.insert("raw".to_string(), self.raw);
+        for (first_piece, count) in &self.by_first_piece {
+            counts.insert(format!("first {}", first_piece), *count);
+        }
+        counts
+    }
+
+    fn from_counts(counts: &BTreeMap<String, u64>) -> Totals {
+        let count = |name: &str| counts.get(name).copied().unwrap_or(0);
+        Totals {
+            solutions: count("solutions"),
+            distinct: count("distinct"),
+            raw: count("raw"),
+            by_first_piece: counts
+                .iter()
+                .filter_map(|(name, count)| {
+                    name.strip_prefix("first ")
+                        .map(|first_piece| (first_piece.to_string(), *count))
+                })
+                .collect(),
+        }
+    }
 }
 
 fn main() {
     let options = CliOptions::from_args();
+    let color = match options.color.unwrap_or(ColorMode::Auto) {
+        ColorMode::Auto => atty::is(atty::Stream::Stdout),
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+    };
+
+    if let Option::Some(requested_pieces) = &options.display_pieces {
+        let board_type = read_in_board_type(&options);
+        if display_pieces(requested_pieces, board_type.pieces(), color).is_err() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    match &options.command {
+        Option::None => {
+            let mut search = start_search(&options);
+            if search.format == OutputFormat::Svg {
+                let mut boards = Vec::new();
+                search.for_each_solution(|solution, _| {
+                    boards.push((solution.placements.to_string(), solution.board))
+                });
+                print!("{}", stacked_svg(&boards));
+                return;
+            }
+            if search.format != OutputFormat::Text {
+                let mut records = Records::new(search.format);
+                search.for_each_solution(|solution, _| records.write(&solution.to_json()));
+                records.write(&search.summary_json(false));
+                records.finish();
+                return;
+            }
+
+            println!(
+                "Finding solutions for {:?} board",
+                search.initial_board.board_type()
+            );
+
+            let display_board = |board: &Board| {
+                if color {
+                    println!("{}", board.colored());
+                } else {
+                    println!("{}", board);
+                }
+            };
+
+            println!("Initial board state");
+            display_board(&search.initial_board);
+            println!("Ending at {}", search.ending_at_description());
+
+            search.for_each_solution(|solution, _| {
+                println!("{}", solution.placements);
+                display_board(&solution.board);
+            });
+            search.print_totals();
+        }
+        Option::Some(Command::Count) => {
+            let mut search = start_search(&options);
+            if search.format == OutputFormat::Text {
+                println!(
+                    "Counting solutions for {:?} board",
+                    search.initial_board.board_type()
+                );
+                println!("Ending at {}", search.ending_at_description());
+            }
+
+            // Solutions that don't have any pieces beyond the initial state (the initial state was
+            // a solution) are counted under "initial state". Orientations have leading zeros, so
+            // the pieces sort the same way as strings.
+            let initial_pieces = search
+                .initial_board
+                .pieces()
+                .names()
+                .iter()
+                .filter(|name| search.initial_board.mask_of(**name).is_some())
+                .count();
+            search.for_each_solution(|solution, totals| {
+                let first_piece = solution
+                    .placements
+                    .path()
+                    .into_iter()
+                    .nth(initial_pieces)
+                    .map_or("initial state".to_string(), |piece| piece.to_string());
+                *totals.by_first_piece.entry(first_piece).or_insert(0) += 1;
+            });
+
+            if search.format != OutputFormat::Text {
+                let mut records = Records::new(search.format);
+                records.write(&search.summary_json(true));
+                records.finish();
+                return;
+            }
+            for (first_piece, count) in &search.totals.by_first_piece {
+                println!("{}: {}", first_piece, count);
+            }
+            search.print_totals();
+        }
+        Option::Some(Command::Validate) => {
+            if !validate(&options, color) {
+                std::process::exit(1);
+            }
+        }
+        Option::Some(Command::Hint { progressive }) => {
+            if !hint(&options, progressive.as_deref(), color) {
+                std::process::exit(1);
+            }
+        }
+        Option::Some(Command::Generate { missing }) => generate(&options, *missing),
+        Option::Some(Command::Rate) => {
+            if !rate_board(&options, color) {
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Rates the board state read from stdin and shows the statistics the rating is based on.
+/// Returns whether the board can be completed.
+fn rate_board(options: &CliOptions, color: bool) -> bool {
+    if options
+        .format
+        .is_some_and(|format| format != OutputFormat::Text)
+    {
+        exit_with_error("--format may not be combined with rate");
+    }
+    // Pruning changes the number of dead ends and pieces tried, so ratings are only comparable
+    // if it is always on.
+    if options.prune_dead_regions.is_some() {
+        exit_with_error("--prune-dead-regions may not be combined with rate");
+    }
+    let board_type = board_type(options);
+    let solver = Solver::new(
+        &board_type,
+        read_in_initial_state(&board_type, options.lenient),
+        false,
+    )
+    .unwrap_or_else(|e| exit_with_error(&e.to_string()));
 
-    let display_pieces: Option<Vec<RequestedPiece>> = options.display_pieces;
-    if display_pieces.is_some() {
-        let requested_pieces = display_pieces.unwrap();
-        for requested_piece in requested_pieces {
-            match (*PIECES).get(&requested_piece.name.to_ascii_uppercase()) {
-                Option::Some(piece) => {
-                    println!("{}", piece);
+    println!("Rating {:?} board", board_type);
+    if color {
+        println!("{}", solver.initial_board().colored());
+    } else {
+        println!("{}", solver.initial_board());
+    }
+
+    let rating = rate(solver);
+    println!("Solutions: {}", rating.solutions);
+    if let Option::Some(nodes) = rating.nodes_to_first_solution {
+        println!("Pieces tried before the first solution: {}", nodes);
+    }
+    println!("Pieces tried: {}", rating.nodes);
+    println!("Dead ends: {}", rating.dead_ends);
+    let branching: Vec<String> = rating
+        .branching
+        .iter()
+        .enumerate()
+        .map(|(depth, branching)| format!("{}: {:.2}", depth + 1, branching))
+        .collect();
+    if !branching.is_empty() {
+        println!("Branching factor by piece: {}", branching.join(", "));
+    }
+
+    match (rating.score(), rating.difficulty()) {
+        (Option::Some(score), Option::Some(difficulty)) => {
+            println!("Score: {:.1} ({})", score, difficulty);
+            true
+        }
+        _ => {
+            println!("The board can't be completed, so it can't be rated.");
+            false
+        }
+    }
+}
+
+/// Writes a challenge with a single solution to stdout, and what was generated to stderr.
+fn generate(options: &CliOptions, missing: usize) {
+    if options
+        .format
+        .is_some_and(|format| format != OutputFormat::Text)
+    {
+        exit_with_error("--format may not be combined with generate");
+    }
+    let board_type = board_type(options);
+    let seed = seed(options);
+
+    let challenge = generate_challenge(&board_type, missing, &mut Random::new(seed))
+        .unwrap_or_else(|| {
+            exit_with_error("The board has no solutions to generate a challenge from")
+        });
+    eprintln!(
+        "Generated a {:?} challenge with {} missing piece(s) (seed {})",
+        board_type,
+        challenge.missing.len(),
+        seed
+    );
+    if challenge.missing.len() < missing {
+        eprintln!(
+            "Removing more pieces would leave more than one solution, so only {} could be removed",
+            challenge.missing.len()
+        );
+    }
+    println!("{}", challenge.board.state().join("\n\n"));
+}
+
+/// The board type from --board-type, or the board read from --board-file, filled with the pieces
+/// read from --pieces. Exits with an error if a file can't be read or the pieces can't fill the
+/// board.
+fn board_type(options: &CliOptions) -> BoardType {
+    let board_type = read_in_board_type(options);
+    board_type
+        .check_cell_count()
+        .unwrap_or_else(|e| exit_with_error(&e.to_string()));
+    board_type
+}
+
+/// Reads the board type (see `board_type`), without checking that the pieces can fill it.
+fn read_in_board_type(options: &CliOptions) -> BoardType {
+    let board_type = match &options.board_file {
+        Option::Some(file) => {
+            if options.board_type.is_some() {
+                exit_with_error("--board-file may not be combined with --board-type");
+            }
+            let name = file_name(file);
+            std::fs::read_to_string(file)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| BoardMask::parse(&contents, &name).map_err(|e| e.to_string()))
+                .map(|mask| BoardType::Custom(Arc::new(mask)))
+                .unwrap_or_else(|e| {
+                    exit_with_error(&format!("Cannot read {}: {}", file.display(), e))
+                })
+        }
+        Option::None => options.board_type.clone().unwrap_or(BoardType::Rectangle),
+    };
+    match &options.pieces {
+        Option::Some(file) => board_type.with_pieces(read_in_pieces(file), &file_name(file)),
+        Option::None => board_type,
+    }
+}
+
+/// The name of the file, the way it is shown in the board type.
+fn file_name(file: &Path) -> String {
+    file.file_name().map_or(file.display().to_string(), |name| {
+        name.to_string_lossy().to_string()
+    })
+}
+
+/// The --seed option, or a seed based on the current time.
+fn seed(options: &CliOptions) -> u64 {
+    options.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+    })
+}
+
+/// Shows the piece that covers the top-most, left-most empty cell in the first solution that
+/// completes the board. Returns whether the board can be completed.
+fn hint(options: &CliOptions, progressive: Option<&Path>, color: bool) -> bool {
+    if options
+        .format
+        .is_some_and(|format| format != OutputFormat::Text)
+    {
+        exit_with_error("--format may not be combined with hint");
+    }
+    let board_type = board_type(options);
+    let state = match progressive.filter(|file| file.exists()) {
+        Option::Some(file) => {
+            let input = std::fs::read_to_string(file).unwrap_or_else(|e| {
+                exit_with_error(&format!("Cannot read {}: {}", file.display(), e))
+            });
+            parse_state(&input, &board_type).unwrap_or_else(|e| {
+                exit_with_error(&format!("Cannot read {}: {}", file.display(), e))
+            })
+        }
+        Option::None => read_in_initial_state(&board_type, options.lenient).unwrap_or_default(),
+    };
+
+    let mut solver = Solver::new(&board_type, Option::Some(state), false)
+        .unwrap_or_else(|e| exit_with_error(&e.to_string()))
+        .with_dead_region_pruning(options.prune_dead_regions.unwrap_or(true));
+    let mut board = solver.initial_board().clone();
+    if board.solved() {
+        println!("The board is already solved.");
+        return true;
+    }
+    let solution = match solver.next() {
+        Option::Some(solution) => solution,
+        Option::None => {
+            println!("The board can't be completed. Try removing some pieces.");
+            return false;
+        }
+    };
+
+    // The piece covering the next position has its top-most, left-most cell there, so it is added
+    // to the board the same way the solver added it.
+    let next_pos = *board.next_pos();
+    let name = solution
+        .board
+        .letter_at(&next_pos)
+        .expect("Every cell of a solution is filled");
+    let piece = solution
+        .placements
+        .path()
+        .into_iter()
+        .find(|piece| piece.name == name)
+        .expect("Every piece of a solution is in its path");
+    let pieces = Arc::clone(board.pieces());
+    board
+        .try_add_shape(&pieces[&name].orientations[piece.orientation_index], name)
+        .expect("The piece fits the same way it did in the solution");
+
+    println!("Hint: place piece {} ({})", name, piece);
+    println!("{}", board.highlighted(name, color));
+    if !color {
+        println!("(piece {} goes in the cells marked *)", name);
+    }
+
+    if let Option::Some(file) = progressive {
+        let mut contents = board.state().join("\n\n");
+        contents.push('\n');
+        std::fs::write(file, contents).unwrap_or_else(|e| {
+            exit_with_error(&format!("Cannot write {}: {}", file.display(), e))
+        });
+        println!("Run the same command again for the next hint.");
+    }
+    true
+}
+
+/// Checks the board state read from stdin and reports on every piece. Returns whether the board
+/// is valid and is (or can be extended to) a solution.
+fn validate(options: &CliOptions, color: bool) -> bool {
+    if options
+        .format
+        .is_some_and(|format| format != OutputFormat::Text)
+    {
+        exit_with_error("--format may not be combined with validate");
+    }
+    let board_type = board_type(options);
+    let state = read_in_initial_state(&board_type, options.lenient).unwrap_or_default();
+    let (board, pieces) = check_pieces(&board_type, &state);
+
+    println!("Validating {:?} board", board_type);
+    if color {
+        println!("{}", board.colored());
+    } else {
+        println!("{}", board);
+    }
+
+    for (name, result) in &pieces {
+        match result {
+            Result::Ok(piece) => println!("{}: valid", piece),
+            Result::Err(e) => println!("{}: {}", name, e),
+        }
+    }
+    let missing: Vec<String> = board
+        .pieces()
+        .names()
+        .iter()
+        .filter(|name| !pieces.iter().any(|(placed, _)| placed == *name))
+        .map(|name| name.to_string())
+        .collect();
+    if !missing.is_empty() {
+        println!("Not placed: {}", missing.join(", "));
+    }
+    println!();
+
+    if pieces.iter().any(|(_, result)| result.is_err()) {
+        println!("The board is not valid.");
+        return false;
+    }
+    if board.solved() {
+        println!("The board is a complete solution.");
+        return true;
+    }
+
+    let solution = Solver::new(&board_type, Option::Some(state), false)
+        .unwrap_or_else(|e| exit_with_error(&e.to_string()))
+        .with_dead_region_pruning(options.prune_dead_regions.unwrap_or(true))
+        .next();
+    match solution {
+        Option::Some(solution) => {
+            println!(
+                "The board can be completed ({} more piece(s)).",
+                solution.placements.path().len() - pieces.len()
+            );
+            true
+        }
+        Option::None => {
+            println!("The board can't be completed.");
+            false
+        }
+    }
+}
+
+/// Writes JSON records to stdout, either as a JSON array or one record per line.
+struct Records {
+    format: OutputFormat,
+    written: usize,
+}
+
+impl Records {
+    fn new(format: OutputFormat) -> Records {
+        Records { format, written: 0 }
+    }
+
+    fn write(&mut self, record: &str) {
+        match self.format {
+            OutputFormat::Json => {
+                print!(
+                    "{}{}",
+                    if self.written == 0 { "[\n" } else { ",\n" },
+                    record
+                );
+            }
+            _ => println!("{}", record),
+        }
+        self.written += 1;
+    }
+
+    fn finish(&self) {
+        if self.format == OutputFormat::Json {
+            println!("{}]", if self.written == 0 { "[" } else { "\n" });
+        }
+    }
+}
+
+/// Displays every requested piece and orientation. Pieces that don't exist are reported, but
+/// don't stop the rest from being displayed.
+fn display_pieces(
+    requested_pieces: &[RequestedPiece],
+    pieces: &PieceSet,
+    color: bool,
+) -> Result<(), Error> {
+    let mut result = Result::Ok(());
+    for requested_piece in requested_pieces {
+        // Piece names are case sensitive, but `a[00]` is piece `A` unless there is a piece `a`.
+        let name = if pieces.contains(&requested_piece.name) {
+            requested_piece.name
+        } else {
+            requested_piece.name.to_ascii_uppercase()
+        };
+        let found = pieces
+            .get(&name)
+            .ok_or(Error::UnknownPiece(requested_piece.name))
+            .and_then(|piece| {
+                piece
+                    .orientations
+                    .get(requested_piece.orientation_index)
+                    .map(|shape| (piece, shape))
+                    .ok_or_else(|| Error::UnknownOrientation(requested_piece.clone()))
+            });
+        match found {
+            Result::Ok((piece, shape)) => {
+                println!("{}", piece);
+                if color {
                     println!(
                         "[{}] => \n{}",
                         requested_piece.orientation_index,
-                        piece
-                            .orientations
-                            .get(requested_piece.orientation_index)
-                            .unwrap()
+                        shape.colored(name, pieces)
                     );
+                } else {
+                    println!("[{}] => \n{}", requested_piece.orientation_index, shape);
+                }
+            }
+            Result::Err(e) => {
+                eprintln!("{}", e);
+                result = Result::Err(e);
+            }
+        }
+    }
+    result
+}
+
+/// Validates the search options, reads in the initial state (or the checkpoint) and creates the
+/// solver.
+fn start_search(options: &CliOptions) -> Search {
+    let allow_backtracking = options.allow_backtracking.unwrap_or(false);
+
+    let engine = options.engine.unwrap_or(Engine::Dfs);
+
+    if options.threads.is_some() && allow_backtracking {
+        exit_with_error("--threads may not be combined with --allow-backtracking");
+    }
+    if engine == Engine::Dlx && (options.threads.is_some() || allow_backtracking) {
+        exit_with_error("--engine dlx may not be combined with --threads or --allow-backtracking");
+    }
+
+    if engine == Engine::Dlx && (options.prune_dead_regions.is_some() || options.stats) {
+        exit_with_error("--engine dlx may not be combined with --prune-dead-regions or --stats");
+    }
+    if options.symmetry.is_some() && (options.ending_at.is_some() || allow_backtracking) {
+        exit_with_error("--symmetry may not be combined with --ending-at or --allow-backtracking");
+    }
+
+    // Resuming continues saving checkpoints to the same file by default.
+    let checkpoint_file = options
+        .checkpoint
+        .clone()
+        .or_else(|| options.resume.clone());
+    if (checkpoint_file.is_some() || options.progress)
+        && (engine == Engine::Dlx || options.threads.is_some())
+    {
+        exit_with_error(
+            "--checkpoint, --resume and --progress may not be combined with --engine dlx or --threads",
+        );
+    }
+    if options.format == Option::Some(OutputFormat::Svg)
+        && matches!(options.command, Option::Some(Command::Count))
+    {
+        exit_with_error("--format svg may not be combined with count");
+    }
+    if options.board_file.is_some() && checkpoint_file.is_some() {
+        exit_with_error("--board-file may not be combined with --checkpoint or --resume");
+    }
+    if options.pieces.is_some() && checkpoint_file.is_some() {
+        exit_with_error("--pieces may not be combined with --checkpoint or --resume");
+    }
+    if options.checkpoint_interval == Option::Some(0) {
+        exit_with_error("--checkpoint-interval must be at least 1 second");
+    }
+    if options.random
+        && (engine == Engine::Dlx
+            || options.ending_at.is_some()
+            || options.symmetry.is_some()
+            || checkpoint_file.is_some()
+            || options.progress)
+    {
+        exit_with_error(
+            "--random may not be combined with --engine dlx, --ending-at, --symmetry, --checkpoint, --resume or --progress",
+        );
+    }
+
+    let mut symmetry = Option::None;
+    let mut stats = Option::None;
+    let mut totals = Totals::default();
+    let (initial_board, solutions_found) = match engine {
+        Engine::Dfs => {
+            let mut solver = match &options.resume {
+                Option::Some(file) => {
+                    let checkpoint = read_in_checkpoint(file);
+                    totals = Totals::from_counts(&checkpoint.counts);
+                    Solver::resume(&checkpoint).unwrap_or_else(|e| {
+                        exit_with_error(&format!("Cannot resume {}: {}", file.display(), e))
+                    })
                 }
                 Option::None => {
-                    println!("Piece {} not found!", &requested_piece.name);
+                    let board_type = board_type(options);
+                    Solver::new(
+                        &board_type,
+                        read_in_initial_state(&board_type, options.lenient),
+                        allow_backtracking,
+                    )
+                    .unwrap_or_else(|e| exit_with_error(&e.to_string()))
                 }
             }
+            .with_dead_region_pruning(options.prune_dead_regions.unwrap_or(true));
+            if options.random {
+                let seed = seed(options);
+                eprintln!("Searching in a random order (seed {})", seed);
+                let order =
+                    SearchOrder::random(solver.initial_board().pieces(), &mut Random::new(seed));
+                solver = solver.with_search_order(Arc::new(order));
+            }
+            let board = solver.initial_board().clone();
+            if options.stats {
+                stats = Option::Some(solver.stats());
+                solver = solver.with_saved_nodes_counted();
+            }
+            if let Option::Some(mode) = options.symmetry {
+                let s = Arc::new(Symmetry::new(&board));
+                solver = solver.with_symmetry(Arc::clone(&s));
+                symmetry = Option::Some((s, mode));
+            }
+            if checkpoint_file.is_none() && !options.progress {
+                let solutions: Box<dyn Iterator<Item = Solution>> = match options.threads {
+                    Option::Some(threads) => Box::new(ParallelSolver::new(
+                        solver,
+                        threads,
+                        options.split_depth.unwrap_or(2),
+                    )),
+                    Option::None => Box::new(solver),
+                };
+                (board, Solutions::All(solutions))
+            } else {
+                let checkpointing = checkpoint_file.map(|file| Checkpointing {
+                    file,
+                    interval: Duration::from_secs(options.checkpoint_interval.unwrap_or(60)),
+                    saved_at: Instant::now(),
+                    interrupted: handle_interruptions(),
+                });
+                let progress = if options.progress {
+                    let on_terminal = atty::is(atty::Stream::Stderr);
+                    Option::Some(Progress {
+                        estimate: ProgressEstimate::new(&solver),
+                        stats: solver.stats(),
+                        on_terminal,
+                        line_shown: false,
+                        interval: Duration::from_secs(if on_terminal { 1 } else { 10 }),
+                        shown_at: Instant::now(),
+                        nodes_when_shown: solver.stats().nodes(),
+                    })
+                } else {
+                    Option::None
+                };
+                (
+                    board,
+                    Solutions::Stepped(Box::new(SteppedSearch {
+                        solver,
+                        checkpointing,
+                        progress,
+                    })),
+                )
+            }
         }
+        Engine::Dlx => {
+            let board_type = board_type(options);
+            let mut solver = DlxSolver::new(
+                &board_type,
+                read_in_initial_state(&board_type, options.lenient),
+            )
+            .unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let board = solver.board().clone();
+            if let Option::Some(mode) = options.symmetry {
+                let s = Arc::new(Symmetry::new(&board));
+                solver = solver.with_symmetry(Arc::clone(&s));
+                symmetry = Option::Some((s, mode));
+            }
+            (board, Solutions::All(Box::new(solver)))
+        }
+    };
 
-        return;
+    // An empty ending path means there is no limit.
+    let ending_at = options
+        .ending_at
+        .clone()
+        .filter(|pieces| !pieces.is_empty())
+        .map(EndingAt::new);
+
+    let solutions = match (solutions_found, ending_at.clone()) {
+        (solutions, Option::None) => solutions,
+        // Since solutions are found in lexical order, we're done as soon as we find one beyond
+        // the ending path. Note: that means we will always find one extra solution (and not
+        // report it) before exiting, but that is more convenient than making the caller define
+        // an exact path to stop at. (If they knew the exact path, they'd already know all the
+        // solutions and wouldn't need to call the solver to begin with).
+        // (A stepped search checks the ending path as it goes. See `next_solution`.)
+        (Solutions::All(solutions), Option::Some(e)) if engine == Engine::Dfs => {
+            Solutions::All(Box::new(solutions.take_while(move |s| e.allows(s))))
+        }
+        (Solutions::All(solutions), Option::Some(e)) => {
+            Solutions::All(Box::new(solutions.filter(move |s| e.allows(s))))
+        }
+        (solutions, Option::Some(_)) => solutions,
+    };
+
+    Search {
+        initial_board,
+        solutions,
+        ending_at,
+        symmetry,
+        stats,
+        totals,
+        remaining: options.limit,
+        format: options.format.unwrap_or(OutputFormat::Text),
+        started: Instant::now(),
     }
+}
 
-    solver::find_solutions(
-        options.board_type,
-        read_in_initial_state(),
-        options.allow_backtracking,
-        options.ending_at,
-    );
+impl Search {
+    fn ending_at_description(&self) -> String {
+        self.ending_at
+            .as_ref()
+            .map_or("NO-LIMIT".to_string(), |e| e.to_string())
+    }
+
+    /// Calls `report` with every solution (up to --limit). When taking advantage of symmetry,
+    /// the solver only finds canonical solutions. In expand mode, the solutions that are
+    /// symmetric to them are reported as well.
+    fn for_each_solution(&mut self, mut report: impl FnMut(Solution, &mut Totals)) {
+        while self.remaining != Option::Some(0) {
+            let solution = match self.next_solution() {
+                Option::Some(solution) => solution,
+                Option::None => break,
+            };
+            let totals = &mut self.totals;
+            let derived = match &self.symmetry {
+                Option::Some((symmetry, mode)) => {
+                    totals.distinct += 1;
+                    totals.raw += symmetry.class_size(&solution.board) as u64;
+                    match mode {
+                        SymmetryMode::Reduce => vec![solution],
+                        SymmetryMode::Expand => symmetry.expand(solution),
+                    }
+                }
+                Option::None => vec![solution],
+            };
+            for solution in derived {
+                if let Option::Some(remaining) = &mut self.remaining {
+                    if *remaining == 0 {
+                        break;
+                    }
+                    *remaining -= 1;
+                }
+                totals.solutions += 1;
+                report(solution, totals);
+            }
+        }
+    }
+
+    /// Finds the next solution to report. A stepped search saves checkpoints and shows its
+    /// progress between steps, since that's when the totals include every solution found so far.
+    fn next_solution(&mut self) -> Option<Solution> {
+        let stepped = match &mut self.solutions {
+            Solutions::All(solutions) => return solutions.next(),
+            Solutions::Stepped(stepped) => stepped,
+        };
+
+        loop {
+            if let Option::Some(checkpointing) = &mut stepped.checkpointing {
+                if checkpointing.interrupted.load(Ordering::SeqCst) {
+                    if let Option::Some(progress) = &mut stepped.progress {
+                        progress.clear();
+                    }
+                    checkpointing.save(&stepped.solver, &self.totals);
+                    eprintln!(
+                        "Interrupted. Continue the search with --resume {}",
+                        checkpointing.file.display()
+                    );
+                    std::process::exit(130);
+                }
+                if checkpointing.saved_at.elapsed() >= checkpointing.interval {
+                    checkpointing.save(&stepped.solver, &self.totals);
+                }
+            }
+            if let Option::Some(progress) = &mut stepped.progress {
+                if progress.shown_at.elapsed() >= progress.interval {
+                    progress.show(&stepped.solver, &self.totals);
+                }
+            }
+
+            let step = stepped.solver.search(NODES_PER_STEP);
+            // The progress line is cleared before anything else is written.
+            if let (Option::Some(progress), SearchStep::Found(_) | SearchStep::Finished) =
+                (&mut stepped.progress, &step)
+            {
+                progress.clear();
+            }
+            match step {
+                // A solution beyond the ending path ends the search, the same way it does for an
+                // iterator. The last checkpoint is left as it is, since the solver has already
+                // moved past that solution without reporting it.
+                SearchStep::Found(solution) => {
+                    return match &self.ending_at {
+                        Option::Some(e) if !e.allows(&solution) => Option::None,
+                        _ => Option::Some(solution),
+                    }
+                }
+                SearchStep::Paused => {}
+                SearchStep::Finished => {
+                    if let Option::Some(checkpointing) = &mut stepped.checkpointing {
+                        checkpointing.save(&stepped.solver, &self.totals);
+                    }
+                    return Option::None;
+                }
+            }
+        }
+    }
+
+    /// The summary record written after the solutions in the JSON formats. It has the same
+    /// totals as the text format, plus the number of seconds the search took.
+    fn summary_json(&self, by_first_piece: bool) -> String {
+        let totals = &self.totals;
+        let mut fields = vec![
+            r#""type":"summary""#.to_string(),
+            format!(
+                r#""board_type":{}"#,
+                json_string(&format!("{:?}", self.initial_board.board_type()))
+            ),
+            format!(r#""solutions":{}"#, totals.solutions),
+        ];
+        if let Option::Some((symmetry, _)) = &self.symmetry {
+            fields.push(format!(r#""distinct":{}"#, totals.distinct));
+            fields.push(format!(r#""including_symmetric":{}"#, totals.raw));
+            fields.push(format!(
+                r#""symmetries":{}"#,
+                symmetry.transformation_count()
+            ));
+        }
+        if by_first_piece {
+            let counts: Vec<String> = totals
+                .by_first_piece
+                .iter()
+                .map(|(first_piece, count)| format!("{}:{}", json_string(first_piece), count))
+                .collect();
+            fields.push(format!(r#""by_first_piece":{{{}}}"#, counts.join(",")));
+        }
+        if let Option::Some(stats) = &self.stats {
+            fields.push(format!(r#""nodes":{}"#, stats.nodes()));
+            fields.push(format!(r#""nodes_saved":{}"#, stats.saved()));
+        }
+        fields.push(format!(
+            r#""elapsed_seconds":{:.3}"#,
+            self.started.elapsed().as_secs_f64()
+        ));
+        format!("{{{}}}", fields.join(","))
+    }
+
+    fn print_totals(&self) {
+        let totals = &self.totals;
+        println!("found {} solutions", totals.solutions);
+
+        if let Option::Some((symmetry, _)) = &self.symmetry {
+            println!(
+                "{} distinct solutions, {} including symmetric solutions ({} {})",
+                totals.distinct,
+                totals.raw,
+                symmetry.transformation_count(),
+                if symmetry.transformation_count() == 1 {
+                    "symmetry"
+                } else {
+                    "symmetries"
+                }
+            );
+        }
+
+        if let Option::Some(stats) = &self.stats {
+            println!(
+                "searched {} nodes, dead region pruning saved {} nodes",
+                stats.nodes(),
+                stats.saved()
+            );
+        }
+    }
+}
+
+impl Checkpointing {
+    /// Writes the checkpoint to a temporary file first and then replaces the checkpoint file
+    /// with it, so an interruption never leaves a partially written checkpoint behind.
+    fn save(&mut self, solver: &Solver, totals: &Totals) {
+        let mut checkpoint = solver.checkpoint();
+        checkpoint.counts = totals.to_counts();
+
+        let mut temporary = self.file.clone().into_os_string();
+        temporary.push(".tmp");
+        let result = std::fs::write(&temporary, checkpoint.to_string())
+            .and_then(|_| std::fs::rename(&temporary, &self.file));
+        if let Result::Err(e) = result {
+            exit_with_error(&format!(
+                "Cannot save the checkpoint to {}: {}",
+                self.file.display(),
+                e
+            ));
+        }
+        self.saved_at = Instant::now();
+    }
+}
+
+impl Progress {
+    /// Shows the estimated fraction of the search that is done, the number of nodes searched per
+    /// second since the progress was last shown, the number of solutions so far and the first
+    /// pieces of the current path.
+    fn show(&mut self, solver: &Solver, totals: &Totals) {
+        let nodes = self.stats.nodes();
+        let nodes_per_second =
+            (nodes - self.nodes_when_shown) as f64 / self.shown_at.elapsed().as_secs_f64();
+
+        let path = solver.path();
+        let done = self
+            .estimate
+            .fraction_done(&path)
+            .map_or("?".to_string(), |fraction| {
+                format!("{:.1}%", fraction * 100.0)
+            });
+        let shown_pieces = self.estimate.initial_pieces() + PROGRESS_PATH_PIECES;
+        let mut path_prefix: Vec<String> = path
+            .iter()
+            .take(shown_pieces)
+            .map(|piece| piece.to_string())
+            .collect();
+        if path.len() > shown_pieces {
+            path_prefix.push("...".to_string());
+        }
+
+        let line = format!(
+            "{} done, {:.0} nodes/s, {} solutions, at {}",
+            done,
+            nodes_per_second,
+            totals.solutions,
+            path_prefix.join("; ")
+        );
+        if self.on_terminal {
+            eprint!("\r\x1b[K{}", line);
+            self.line_shown = true;
+        } else {
+            eprintln!("{}", line);
+        }
+
+        self.shown_at = Instant::now();
+        self.nodes_when_shown = nodes;
+    }
+
+    /// Removes the progress line from the terminal, so it doesn't get mixed up with other
+    /// output.
+    fn clear(&mut self) {
+        if self.line_shown {
+            eprint!("\r\x1b[K");
+            self.line_shown = false;
+        }
+    }
+}
+
+/// Sets a flag when the search is interrupted (Ctrl+C) instead of exiting right away.
+fn handle_interruptions() -> Arc<AtomicBool> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_flag = Arc::clone(&interrupted);
+    ctrlc::set_handler(move || handler_flag.store(true, Ordering::SeqCst))
+        .unwrap_or_else(|e| exit_with_error(&format!("Cannot handle Ctrl+C: {}", e)));
+    interrupted
 }
 
-fn read_in_initial_state() -> Option<Vec<String>> {
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn read_in_pieces(file: &Path) -> PieceSet {
+    std::fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| PieceSet::parse(&contents).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| exit_with_error(&format!("Cannot read {}: {}", file.display(), e)))
+}
+
+fn read_in_checkpoint(file: &Path) -> Checkpoint {
+    std::fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| contents.parse::<Checkpoint>().map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| exit_with_error(&format!("Cannot read {}: {}", file.display(), e)))
+}
+
+/// Reads the initial state from stdin (see `state::parse_state`). Exits with an error if it is
+/// not a valid state for the board, unless `lenient` is set.
+fn read_in_initial_state(board_type: &BoardType, lenient: bool) -> Option<Vec<String>> {
     if atty::is(atty::Stream::Stdin) {
         return Option::None;
     }
 
     let mut stdin = std::io::stdin();
     let mut input = String::new();
-    match stdin.read_to_string(&mut input) {
-        _ => (),
-    }
+    // An unreadable stdin is treated the same as an empty initial state.
+    let _ = stdin.read_to_string(&mut input);
 
-    let lines: Vec<String> = input.split("\n\n").map(|s| s.to_string()).collect();
-    Option::Some(lines)
+    if lenient {
+        Option::Some(parse_state_leniently(&input))
+    } else {
+        Option::Some(
+            parse_state(&input, board_type).unwrap_or_else(|e| exit_with_error(&e.to_string())),
+        )
+    }
 }
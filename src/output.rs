@@ -0,0 +1,119 @@
+use crate::layer::Position;
+use crate::solver::Solution;
+use std::str::FromStr;
+
+/// How solutions are written out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// The placements path and the board, the way they are displayed.
+    Text,
+    /// A JSON array with a record for every solution (see `Solution::to_json`), followed by a
+    /// summary record.
+    Json,
+    /// The same records as `Json`, one per line ([newline delimited JSON](http://ndjson.org)).
+    Ndjson,
+    /// An SVG image of every solution, one below the other. (See `svg::stacked_svg`)
+    Svg,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("text") {
+            Result::Ok(OutputFormat::Text)
+        } else if s.eq_ignore_ascii_case("json") {
+            Result::Ok(OutputFormat::Json)
+        } else if s.eq_ignore_ascii_case("ndjson") {
+            Result::Ok(OutputFormat::Ndjson)
+        } else if s.eq_ignore_ascii_case("svg") {
+            Result::Ok(OutputFormat::Svg)
+        } else {
+            Result::Err(format!(
+                "Unknown format {} (expected text, json, ndjson or svg)",
+                s
+            ))
+        }
+    }
+}
+
+impl Solution {
+    /// The solution as a single line JSON object:
+    ///
+    /// ```json
+    /// {"type":"solution","board_type":"Rectangle","path":["A[00]","B[03]",...],
+    ///  "pieces":[{"name":"A","orientation":0,"cells":[[0,0,0],[0,0,1],[0,0,2],[0,1,0]]},...],
+    ///  "grid":[["AAABB...","A.BBB..."]]}
+    /// ```
+    ///
+    /// The pieces are listed in path order. Cells are `[layer, row, col]`. The grid has an array
+    /// of rows for every layer, in the same format as an initial state.
+    pub fn to_json(&self) -> String {
+        let path = self.placements.path();
+        let pieces: Vec<String> = path
+            .iter()
+            .map(|piece| {
+                let cells: Vec<String> = self
+                    .board
+                    .mask_of(piece.name)
+                    .map(|mask| {
+                        self.board
+                            .masks()
+                            .positions_in(mask)
+                            .collect::<Vec<&Position>>()
+                    })
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|Position(layer, row, col)| format!("[{},{},{}]", layer, row, col))
+                    .collect();
+                format!(
+                    r#"{{"name":{},"orientation":{},"cells":[{}]}}"#,
+                    json_string(&piece.name.to_string()),
+                    piece.orientation_index,
+                    cells.join(",")
+                )
+            })
+            .collect();
+        let grid: Vec<String> = self
+            .board
+            .state()
+            .iter()
+            .map(|layer| format!("[{}]", json_strings(layer.lines())))
+            .collect();
+
+        format!(
+            r#"{{"type":"solution","board_type":{},"path":[{}],"pieces":[{}],"grid":[{}]}}"#,
+            json_string(&format!("{:?}", self.board.board_type())),
+            json_strings(path.iter().map(|piece| piece.to_string())),
+            pieces.join(","),
+            grid.join(",")
+        )
+    }
+}
+
+/// Quotes a string for JSON, escaping the characters that have to be escaped.
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quotes every string and separates them with commas (the inside of a JSON array).
+fn json_strings<S: AsRef<str>>(strings: impl Iterator<Item = S>) -> String {
+    strings
+        .map(|s| json_string(s.as_ref()))
+        .collect::<Vec<String>>()
+        .join(",")
+}
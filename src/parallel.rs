@@ -0,0 +1,259 @@
+use crate::solver::{SearchStep, Solution, Solver};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// The number of solutions of a subtree that can be waiting to be returned before the worker
+/// searching it waits for the caller to catch up.
+const SOLUTIONS_PER_SUBTREE: usize = 64;
+
+/// The number of subtrees (per worker) that can be handed out beyond the one whose solutions are
+/// being returned. Together with `SOLUTIONS_PER_SUBTREE` this limits how many solutions are held
+/// back at any time.
+const SUBTREES_AHEAD_PER_WORKER: usize = 2;
+
+/// The number of nodes a worker searches before it checks whether the search was cancelled, so
+/// it stops soon even in a subtree without solutions.
+const NODES_PER_CHECK: u64 = 100_000;
+
+enum Message {
+    Found(Box<Solution>),
+    Finished,
+}
+
+/// A subtree that hasn't been handed to a worker yet, and where to send its solutions.
+struct Subtree {
+    index: usize,
+    solver: Solver,
+    sender: SyncSender<Message>,
+}
+
+/// The subtrees waiting for a worker. Workers only take a subtree once it is close enough to the
+/// subtree whose solutions are being returned (see `SUBTREES_AHEAD_PER_WORKER`).
+struct Queue {
+    subtrees: VecDeque<Subtree>,
+    /// The index of the subtree whose solutions are being returned.
+    current: usize,
+    /// The number of subtrees from `current` on that may be handed out.
+    window: usize,
+    cancelled: bool,
+}
+
+/// The queue, and the condition workers wait on until the next subtree may be handed out.
+type SharedQueue = Arc<(Mutex<Queue>, Condvar)>;
+
+/// Finds solutions on multiple threads.
+///
+/// The search tree is split into subtrees (see `Solver::split`) that are handed to a pool of
+/// worker threads. Each worker searches one subtree at a time. Solutions are returned in the
+/// same (lexical) order a single `Solver` would return them: solutions from a subtree are
+/// held back until every subtree before it has been fully searched. Only a few subtrees beyond
+/// the current one are searched at a time, and each of them only holds back a limited number of
+/// solutions, so a slow caller doesn't make the solutions pile up.
+///
+/// If a worker panics, the panic is passed on to the caller once it reaches the subtree the
+/// worker was searching.
+pub struct ParallelSolver {
+    /// The solutions of every subtree that hasn't been fully returned yet, in order.
+    receivers: VecDeque<Receiver<Message>>,
+    queue: SharedQueue,
+    cancelled: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ParallelSolver {
+    /// Splits the solver's search tree by placing up to `split_depth` more pieces and starts
+    /// `threads` workers to search the subtrees.
+    pub fn new(solver: Solver, threads: usize, split_depth: usize) -> ParallelSolver {
+        let threads = threads.max(1);
+        let mut receivers = VecDeque::new();
+        let mut subtrees = VecDeque::new();
+        for (index, solver) in solver.split(split_depth).into_iter().enumerate() {
+            let (sender, receiver) = sync_channel(SOLUTIONS_PER_SUBTREE);
+            receivers.push_back(receiver);
+            subtrees.push_back(Subtree {
+                index,
+                solver,
+                sender,
+            });
+        }
+
+        let queue = Arc::new((
+            Mutex::new(Queue {
+                subtrees,
+                current: 0,
+                window: threads * SUBTREES_AHEAD_PER_WORKER,
+                cancelled: false,
+            }),
+            Condvar::new(),
+        ));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let workers = (0..threads)
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                let cancelled = Arc::clone(&cancelled);
+                thread::spawn(move || search_subtrees(queue, cancelled))
+            })
+            .collect();
+
+        ParallelSolver {
+            receivers,
+            queue,
+            cancelled,
+            workers,
+        }
+    }
+
+    /// Stops every worker within the next few nodes (see `NODES_PER_CHECK`) and waits for them.
+    /// Returns the panic of the first worker that panicked, if one did.
+    fn stop_workers(&mut self) -> Option<Box<dyn Any + Send>> {
+        self.cancelled.store(true, Ordering::Relaxed);
+        let (queue, advanced) = &*self.queue;
+        queue.lock().unwrap().cancelled = true;
+        advanced.notify_all();
+        // Workers waiting for room to send a solution give up once nobody is listening.
+        self.receivers.clear();
+
+        let mut panicked = Option::None;
+        for worker in self.workers.drain(..) {
+            if let Result::Err(payload) = worker.join() {
+                panicked.get_or_insert(payload);
+            }
+        }
+        panicked
+    }
+}
+
+/// Worker loop. Takes subtrees off the queue until it is empty (or the search is cancelled) and
+/// sends back everything it finds.
+fn search_subtrees(queue: SharedQueue, cancelled: Arc<AtomicBool>) {
+    while let Option::Some(Subtree {
+        mut solver, sender, ..
+    }) = next_subtree(&queue)
+    {
+        loop {
+            if cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            match solver.search(NODES_PER_CHECK) {
+                SearchStep::Found(solution) => {
+                    if sender.send(Message::Found(Box::new(solution))).is_err() {
+                        return;
+                    }
+                }
+                SearchStep::Paused => {}
+                SearchStep::Finished => break,
+            }
+        }
+
+        if sender.send(Message::Finished).is_err() {
+            return;
+        }
+    }
+}
+
+/// Takes the next subtree off the queue, waiting until it is close enough to the subtree whose
+/// solutions are being returned. Returns `None` once the queue is empty or the search was
+/// cancelled.
+fn next_subtree(queue: &SharedQueue) -> Option<Subtree> {
+    let (queue, advanced) = &**queue;
+    let mut queue = queue.lock().unwrap();
+    loop {
+        if queue.cancelled {
+            return Option::None;
+        }
+        match queue.subtrees.front() {
+            Option::None => return Option::None,
+            Option::Some(subtree) if subtree.index < queue.current + queue.window => {
+                return queue.subtrees.pop_front();
+            }
+            Option::Some(_) => queue = advanced.wait(queue).unwrap(),
+        }
+    }
+}
+
+impl Iterator for ParallelSolver {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        loop {
+            let receiver = match self.receivers.front() {
+                Option::Some(receiver) => receiver,
+                Option::None => {
+                    // Every subtree has been searched, so the workers are done too.
+                    if let Option::Some(payload) = self.stop_workers() {
+                        panic::resume_unwind(payload);
+                    }
+                    return Option::None;
+                }
+            };
+
+            // Anything found in later subtrees is held back by their workers until it is their
+            // turn.
+            match receiver.recv() {
+                Result::Ok(Message::Found(solution)) => return Option::Some(*solution),
+                Result::Ok(Message::Finished) => {
+                    // Everything in the current subtree has been returned. Move on to the next
+                    // one, which lets the workers take another subtree.
+                    self.receivers.pop_front();
+                    let (queue, advanced) = &*self.queue;
+                    queue.lock().unwrap().current += 1;
+                    advanced.notify_all();
+                }
+                // The worker searching the subtree is gone before it finished. Workers only stop
+                // early when the search is cancelled, so it panicked.
+                Result::Err(_) => {
+                    let payload = self
+                        .stop_workers()
+                        .expect("Workers only stop before finishing a subtree if they panic");
+                    panic::resume_unwind(payload);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ParallelSolver {
+    fn drop(&mut self) {
+        // Nobody is listening anymore. A worker's panic is dropped rather than raised here,
+        // since the caller doesn't need any more solutions.
+        let _ = self.stop_workers();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_panics_are_passed_on() {
+        let (sender, receiver) = sync_channel::<Message>(SOLUTIONS_PER_SUBTREE);
+        let worker = thread::spawn(move || {
+            let _sender = sender;
+            panic!("The worker failed");
+        });
+        let mut solver = ParallelSolver {
+            receivers: VecDeque::from([receiver]),
+            queue: Arc::new((
+                Mutex::new(Queue {
+                    subtrees: VecDeque::new(),
+                    current: 0,
+                    window: SUBTREES_AHEAD_PER_WORKER,
+                    cancelled: false,
+                }),
+                Condvar::new(),
+            )),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            workers: vec![worker],
+        };
+
+        let payload = panic::catch_unwind(panic::AssertUnwindSafe(|| solver.next()))
+            .err()
+            .unwrap();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"The worker failed"));
+    }
+}
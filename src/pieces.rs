@@ -1,16 +1,17 @@
+use crate::color::{ansi_cell, Colored};
+use crate::error::Error;
+use crate::layer::GridLayers;
 use crate::Layers;
 use once_cell::sync::Lazy;
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
+use std::ops::Index;
+use std::sync::Arc;
 
-define_layers!(ShapeLayers, bool, false,
-    { layer: 0, rows: 5, cols: 5 },
-    { layer: 1, rows: 4, cols: 4 },
-    { layer: 2, rows: 3, cols: 3 },
-    { layer: 3, rows: 2, cols: 2 },
-    { layer: 4, rows: 1, cols: 1 }
-);
+/// The smallest base layer a shape has, so the Kanoodle pieces (which have 5 cells at the most)
+/// all have the same layers.
+const MIN_SHAPE_SIZE: usize = 5;
 
 enum ShiftInstruction {
     Up,
@@ -22,9 +23,13 @@ enum ShiftInstruction {
 
 /// Defines a specific Kanoodle shape
 ///
+/// A shape has square layers that get smaller by one row and column per layer, like the pyramid
+/// board. The base layer is 5 x 5, or as big as the number of cells for bigger pieces (such as
+/// hexominoes), which leaves room for every orientation of the piece (see `Shape::size_for`).
+///
 /// # Example
 /// L-shape (`A`)
-/// ```
+/// ```text
 /// [[true, false, false, false, false],
 /// [true, false, false, false, false],
 /// [true, true, false, false, false],
@@ -33,11 +38,28 @@ enum ShiftInstruction {
 /// ```
 #[derive(Clone, Hash, Eq, PartialEq)]
 pub struct Shape {
-    layers: ShapeLayers,
+    layers: GridLayers<bool>,
     pub is_3d: bool,
 }
 
 impl Shape {
+    /// Creates a shape without any cells set, with a base layer of `size` x `size` cells.
+    fn empty(size: usize) -> Shape {
+        let dimensions: Vec<(usize, usize)> = (1..=size).rev().map(|n| (n, n)).collect();
+        Shape {
+            layers: GridLayers::new(&dimensions, false),
+            is_3d: false,
+        }
+    }
+
+    /// The size of the base layer of a shape with this many cells. A piece lies flat in a
+    /// rectangle of `rows + cols - 1` cells at the most (fewer cells couldn't connect every row
+    /// and column), so the piece fits on the diagonal of the base layer in every orientation,
+    /// including the ones that stand up (see `erect`).
+    pub fn size_for(cell_count: usize) -> usize {
+        max(MIN_SHAPE_SIZE, cell_count)
+    }
+
     // If the cell at the specified layer, row, and column is set, that
     // cell is part of the shape.
     pub fn is_set(&self, layer: usize, row: usize, col: usize) -> bool {
@@ -48,8 +70,24 @@ impl Shape {
         self.layers.dimensions(layer)
     }
 
-    pub const fn layer_count(&self) -> usize {
-        ShapeLayers::layer_count()
+    pub fn layer_count(&self) -> usize {
+        self.layers.layer_count()
+    }
+
+    /// The number of cells that are part of the shape.
+    pub fn cell_count(&self) -> usize {
+        let mut count = 0;
+        for layer in 0..self.layer_count() {
+            let (row_count, col_count) = self.dimensions(layer);
+            for row in 0..row_count {
+                for col in 0..col_count {
+                    if self.is_set(layer, row, col) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
     }
 
     /// Creates a new shape that has been rotated 90 degrees clockwise
@@ -78,22 +116,11 @@ impl Shape {
     pub fn snap_to_top_left(&self) -> Shape {
         // Shift up until we find a non-falsy value in a row at any layer.
         let mut layers = shift(&self.layers, |ls| {
-            if !ls.0[0][0]
-                && !ls.0[0][1]
-                && !ls.0[0][2]
-                && !ls.0[0][3]
-                && !ls.0[0][4]
-                && !ls.1[0][0]
-                && !ls.1[0][1]
-                && !ls.1[0][2]
-                && !ls.1[0][3]
-                && !ls.2[0][0]
-                && !ls.2[0][1]
-                && !ls.2[0][2]
-                && !ls.3[0][0]
-                && !ls.3[0][1]
-                && !ls.4[0][0]
-            {
+            let row_is_empty = (0..ls.layer_count()).all(|layer| {
+                let (_, col_count) = ls.dimensions(layer);
+                (0..col_count).all(|col| !ls.at(layer, 0, col))
+            });
+            if row_is_empty {
                 ShiftInstruction::Up
             } else {
                 ShiftInstruction::Stop
@@ -102,32 +129,21 @@ impl Shape {
 
         // Shift left until we find a non-falsy value in a column at any layer.
         layers = shift(&layers, |ls| {
-            if !ls.0[0][0]
-                && !ls.0[1][0]
-                && !ls.0[2][0]
-                && !ls.0[3][0]
-                && !ls.0[4][0]
-                && !ls.1[0][0]
-                && !ls.1[1][0]
-                && !ls.1[2][0]
-                && !ls.1[3][0]
-                && !ls.2[0][0]
-                && !ls.2[1][0]
-                && !ls.2[2][0]
-                && !ls.3[0][0]
-                && !ls.3[1][0]
-                && !ls.4[0][0]
-            {
+            let col_is_empty = (0..ls.layer_count()).all(|layer| {
+                let (row_count, _) = ls.dimensions(layer);
+                (0..row_count).all(|row| !ls.at(layer, row, 0))
+            });
+            if col_is_empty {
                 ShiftInstruction::Left
             } else {
                 ShiftInstruction::Stop
             }
         });
 
-        return Shape {
+        Shape {
             layers,
             is_3d: self.is_3d,
-        };
+        }
     }
 
     pub fn erect(&self) -> Shape {
@@ -151,14 +167,16 @@ impl Shape {
 
         // The shape isn't 3d right now so we only have to worry about layer 0 when
         // shifting it.
-        let layer0 = &mut shift(&aligned.layers, |ls| {
-            if ls.0[0][1] || ls.0[1][2] || ls.0[2][3] || ls.0[3][4] {
+        let layer0 = shift(&aligned.layers, |ls| {
+            let (size, _) = ls.dimensions(0);
+            let right_of_diagonal =
+                (0..size).any(|row| (row + 1..size).any(|col| *ls.at(0, row, col)));
+            if right_of_diagonal {
                 ShiftInstruction::Down
             } else {
                 ShiftInstruction::Stop
             }
-        })
-        .0;
+        });
 
         // Erect the shape to the north-east keeping all pieces on the tl-br diagonal as
         // the pivots.
@@ -171,41 +189,26 @@ impl Shape {
 
         // Pieces on the center diagonal remain in layer 0
         // The next diagonal to the left is layer 1, the next is layer 2, etc.
-        // Start at layer4 and work backwards.
-
-        let layer4 = [[layer0[4][0]]];
-
-        let layer3 = [[layer0[3][0], false], [false, layer0[4][1]]];
-
-        let layer2 = [
-            [layer0[2][0], false, false],
-            [false, layer0[3][1], false],
-            [false, false, layer0[4][2]],
-        ];
-
-        let layer1 = [
-            [layer0[1][0], false, false, false],
-            [false, layer0[2][1], false, false],
-            [false, false, layer0[3][2], false],
-            [false, false, false, layer0[4][3]],
-        ];
-
-        // Now that we've copied the proper values into the layers above 0, we can
-        // update layer0 to clear out any values that moved to a different layer.
-        // This means that all bools other than then ones in the tl-br diagonal should
-        // be set to false.
-        for i in 0..5 {
-            for j in 0..5 {
-                if i != j {
-                    layer0[i][j] = false;
+        // Every layer only has its own diagonal set: the cell in row `i` of layer `n` comes from
+        // row `i + n` of layer 0.
+        // This means that all bools other than then ones in the tl-br diagonal are false,
+        // including the ones in layer 0 that moved to a different layer.
+        let mut layers = layer0.clone();
+        let mut is_3d = false;
+        for layer in 0..layers.layer_count() {
+            let (size, _) = layers.dimensions(layer);
+            for row in 0..size {
+                for col in 0..size {
+                    let value = row == col && *layer0.at(0, row + layer, col);
+                    layers.update(layer, row, col, value);
+                    is_3d |= value && layer > 0;
                 }
             }
         }
 
-        return Shape {
-            layers: ShapeLayers(*layer0, layer1, layer2, layer3, layer4),
-            is_3d: true,
-        };
+        // A single cell stays in layer 0, so it is still flat (and the same as a flat
+        // orientation).
+        Shape { layers, is_3d }
     }
 
     /// Parses a vector of strings into a shape. This vector may contain
@@ -215,33 +218,34 @@ impl Shape {
     /// Each string in the vector represents a shape layer (to support
     /// parsing 3d shapes). Rows in a layer are separated by new lines.
     ///
+    /// Returns `None` if the letter isn't in the strings, or if its cells don't fit in the layers
+    /// of a shape with that many cells (see `Shape::size_for`).
+    ///
     /// # Examples
     ///
     /// Given the vector:
-    /// ```
+    /// ```text
     /// ["AAABB\nA.BBB"]
     /// ```
     /// Shapes `A` and `B` can be parsed from it.
     ///
     /// Given the vector:
-    /// ```
+    /// ```text
     /// ["A..\n...\n...\n",  "A.\n.A\n", "A"]
     /// ```
     /// 3D shape `A` can be parsed from it.
-    /// ```
+    /// ```text
     ///   A
     ///  A A
     /// A . .
     /// ```
-    pub fn parse(strings: &Vec<String>, letter: char) -> Option<Shape> {
-        // First we need to figure out if the shape is offset.
-        // Shapes are 5x5 at the most. If we're parsing a string
-        // that is larger than that, we need to make sure we don't
-        // overflow the shape dimensions.
-        let mut row_offset = usize::MAX;
-        let mut col_offset = usize::MAX;
-
-        strings.iter().for_each(|string| {
+    pub fn parse(strings: &[String], letter: char) -> Option<Shape> {
+        // First we need to figure out if the shape is offset, and how many cells it has.
+        // The shape's layers are only as big as its cells need them to be. If we're parsing a
+        // string that is larger than that, we need to make sure we don't overflow the shape
+        // dimensions.
+        let mut cells = Vec::new();
+        for (layer, string) in strings.iter().enumerate() {
             let mut row = 0usize;
             let mut col = 0usize;
             for ch in string.chars() {
@@ -251,60 +255,43 @@ impl Shape {
                         col = 0;
                     }
                     c if c == letter => {
-                        row_offset = row_offset.min(row);
-                        col_offset = col_offset.min(col);
+                        cells.push((layer, row, col));
                         col += 1;
                     }
                     _ => col += 1,
                 }
             }
-        });
+        }
+        let row_offset = cells.iter().map(|(_, row, _)| *row).min()?;
+        let col_offset = cells.iter().map(|(_, _, col)| *col).min()?;
 
         // Now that we have our offsets, we can actually create the shape.
-        let mut layers = ShapeLayers::default();
-        let layer_count = strings.len();
+        let mut shape = Shape::empty(Shape::size_for(cells.len()));
         let mut layers_with_shape = HashSet::new();
-
-        for layer in 0..layer_count {
-            let string: &String = strings.get(layer).unwrap();
-            let mut row = 0usize;
-            let mut col = 0usize;
-            for ch in string.chars() {
-                match ch {
-                    '\n' => {
-                        row += 1;
-                        col = 0;
-                    }
-                    c if c == letter => {
-                        // These offset subtractions should never overflow since they are always the
-                        // minimum possible values. They should always be <= row and col everywhere.
-                        layers.update(layer, row - row_offset, col - col_offset, true);
-                        layers_with_shape.insert(layer);
-                        col += 1;
-                    }
-                    _ => col += 1,
-                }
+        for (layer, row, col) in cells {
+            // These offset subtractions should never overflow since they are always the
+            // minimum possible values. They should always be <= row and col everywhere.
+            let (row, col) = (row - row_offset, col - col_offset);
+            if layer >= shape.layer_count() {
+                return Option::None;
             }
+            let (row_count, col_count) = shape.dimensions(layer);
+            if row >= row_count || col >= col_count {
+                return Option::None;
+            }
+            shape.layers.update(layer, row, col, true);
+            layers_with_shape.insert(layer);
         }
 
-        if layers.find(&true).is_some() {
-            Option::Some(
-                Shape {
-                    layers,
-                    is_3d: layers_with_shape.len() > 1,
-                }
-                .snap_to_top_left(),
-            )
-        } else {
-            Option::None
-        }
+        shape.is_3d = layers_with_shape.len() > 1;
+        Option::Some(shape.snap_to_top_left())
     }
 }
 
 fn shift(
-    layers: &ShapeLayers,
-    next_instruction: fn(to_be_shifted: &ShapeLayers) -> ShiftInstruction,
-) -> ShapeLayers {
+    layers: &GridLayers<bool>,
+    next_instruction: fn(to_be_shifted: &GridLayers<bool>) -> ShiftInstruction,
+) -> GridLayers<bool> {
     let mut source = layers.clone();
     let mut dest = layers.clone();
 
@@ -355,8 +342,8 @@ fn shift(
 }
 
 fn transform_each_layer_2d(
-    layers: &ShapeLayers,
-    to_be_transformed: &mut ShapeLayers,
+    layers: &GridLayers<bool>,
+    to_be_transformed: &mut GridLayers<bool>,
     transformer: fn(usize, usize, usize) -> (usize, usize),
 ) {
     let layer_count = layers.layer_count();
@@ -381,32 +368,61 @@ fn transform_each_layer_2d(
 const EMPTY_CELL: &str = "·";
 const FILLED_CELL: &str = "●";
 
-impl Display for Shape {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut vec = Vec::new();
-        if self.is_3d {
-            append_layer(&mut vec, &self.layers.4);
-            append_layer(&mut vec, &self.layers.3);
-            append_layer(&mut vec, &self.layers.2);
-            append_layer(&mut vec, &self.layers.1);
+impl Shape {
+    /// Displays the shape with the filled cells in the color of the piece in its set (see
+    /// `color::piece_color`).
+    pub fn colored<'a>(&'a self, letter: char, pieces: &'a PieceSet) -> Colored<'a, Self> {
+        Colored {
+            value: self,
+            letter,
+            pieces,
         }
+    }
 
-        for row in self.layers.0 {
-            for col in row {
-                vec.push(if col { FILLED_CELL } else { EMPTY_CELL })
+    fn fmt_cells(&self, f: &mut Formatter<'_>, filled_cell: &str) -> std::fmt::Result {
+        let mut vec = Vec::new();
+        if self.is_3d {
+            for layer in (1..self.layer_count()).rev() {
+                append_layer(&mut vec, &self.layers, layer, filled_cell);
             }
-            vec.push("\n")
         }
+
+        append_layer(&mut vec, &self.layers, 0, filled_cell);
+        // The base layer isn't followed by an empty line.
+        vec.pop();
         writeln!(f, "{}", vec.join(""))?;
 
         Result::Ok(())
     }
 }
 
-fn append_layer<Level: AsRef<[Row]>, Row: AsRef<[bool]>>(vec: &mut Vec<&str>, bools: Level) {
-    for row in bools.as_ref() {
-        for col in row.as_ref() {
-            vec.push(if *col { FILLED_CELL } else { EMPTY_CELL });
+impl Display for Shape {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_cells(f, FILLED_CELL)
+    }
+}
+
+impl Display for Colored<'_, Shape> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.value
+            .fmt_cells(f, &ansi_cell(self.letter, self.pieces, FILLED_CELL))
+    }
+}
+
+fn append_layer<'a>(
+    vec: &mut Vec<&'a str>,
+    layers: &GridLayers<bool>,
+    layer: usize,
+    filled_cell: &'a str,
+) {
+    let (row_count, col_count) = layers.dimensions(layer);
+    for row in 0..row_count {
+        for col in 0..col_count {
+            vec.push(if *layers.at(layer, row, col) {
+                filled_cell
+            } else {
+                EMPTY_CELL
+            });
         }
         vec.push("\n");
     }
@@ -415,8 +431,8 @@ fn append_layer<Level: AsRef<[Row]>, Row: AsRef<[bool]>>(vec: &mut Vec<&str>, bo
 
 /// Defines a Kanoodle piece.
 ///
-/// A piece consists of a name (letter) A - L, and a vector that contains
-/// all possible orientations it may be legally by placed in.
+/// A piece consists of a name (letter) A - L (or any other symbol, see `PieceSet::parse`), and a
+/// vector that contains all possible orientations it may be legally by placed in.
 pub struct Piece {
     pub letter: String,
     pub orientations: Vec<Shape>,
@@ -443,10 +459,7 @@ impl Piece {
         let to_parse = vec![value.to_string()];
         let shape = Shape::parse(&to_parse, letter).unwrap();
 
-        let orientations = generate_orientations(shape.layers.0)
-            .into_iter()
-            .map(|s| s)
-            .collect();
+        let orientations = generate_orientations(&shape);
 
         Piece {
             letter: letter.to_string(),
@@ -455,37 +468,26 @@ impl Piece {
     }
 }
 
-/// Given a single shape, this function determines all possible orientations of that
+/// Given a single (flat) shape, this function determines all possible orientations of that
 /// shape and returns them in a vector.
 ///
 /// The orientations in the vector returned in a consistent order.
 ///
 /// `to_int` determines the sort order.
-fn generate_orientations(cells: [[bool; 5]; 5]) -> Vec<Shape> {
+fn generate_orientations(shape: &Shape) -> Vec<Shape> {
     let mut set = HashSet::new();
-    let mut layer0 = [[false; 5]; 5];
-    for i in 0..5 {
-        for j in 0..5 {
-            layer0[i][j] = cells[i][j];
-        }
-    }
-
-    let shape = Shape {
-        layers: ShapeLayers(
-            layer0,
-            [[false; 4]; 4],
-            [[false; 3]; 3],
-            [[false; 2]; 2],
-            [[false; 1]; 1],
-        ),
-        is_3d: false,
-    };
 
-    add_rotated_orientations(&shape, &mut set);
-    add_mirrored_orientations(&shape, &mut set);
+    add_rotated_orientations(shape, &mut set);
+    add_mirrored_orientations(shape, &mut set);
 
     let mut vec: Vec<Shape> = set.into_iter().collect();
-    vec.sort_by(|s1, s2| to_int(s1).cmp(&to_int(s2)));
+    // Different shapes can have the same int representation. Comparing the layers breaks those
+    // ties, otherwise the order would depend on the set's (random) iteration order.
+    vec.sort_by(|s1, s2| {
+        to_int(s1)
+            .cmp(&to_int(s2))
+            .then_with(|| s1.layers.cmp(&s2.layers))
+    });
 
     vec
 }
@@ -496,7 +498,10 @@ fn add_rotated_orientations(shape: &Shape, set: &mut HashSet<Shape>) {
         set.insert(next.snap_to_top_left());
         if !&next.is_3d {
             // Add rotated 3d orientations.
-            add_rotated_orientations(&next.erect(), set);
+            let erected = next.erect();
+            if erected.is_3d {
+                add_rotated_orientations(&erected, set);
+            }
         }
         // The last rotate in the loop brings us back to 0, so we can toss it.
         next = next.rotate();
@@ -507,24 +512,26 @@ fn add_mirrored_orientations(shape: &Shape, set: &mut HashSet<Shape>) {
     add_rotated_orientations(&shape.reflect(), set);
 }
 
+/// An int that orders the orientations of a piece. Shapes with more than 5 rows or layers wrap
+/// around, which still orders them consistently (ties are broken in `generate_orientations`).
 pub fn to_int(shape: &Shape) -> u64 {
     let mut int_repr: u64 = 0;
     let mut layer_bonus: u64 = 1;
 
-    for layer in 0..ShapeLayers::layer_count() {
+    for layer in 0..shape.layer_count() {
         layer_bonus = match layer {
             0 => 1,
             1 => 10000,
-            _ => layer_bonus * 100,
+            _ => layer_bonus.wrapping_mul(100),
         };
 
         let (row_count, col_count) = shape.layers.dimensions(layer);
 
         for row in 0..row_count {
-            let row_bonus: u64 = 10u64.pow(row as u32) * layer_bonus;
+            let row_bonus: u64 = 10u64.wrapping_pow(row as u32).wrapping_mul(layer_bonus);
             for col in 0..col_count {
                 if *shape.layers.at(layer, row, col) {
-                    int_repr += (col as u32 + 1u32) as u64 * row_bonus;
+                    int_repr = int_repr.wrapping_add((col as u64 + 1).wrapping_mul(row_bonus));
                 }
             }
         }
@@ -532,118 +539,319 @@ pub fn to_int(shape: &Shape) -> u64 {
 
     int_repr
 }
+/// The characters that are empty cells in a piece file (see `PieceSet::parse`) and in a board
+/// state (see `state::parse_state`).
+pub(crate) const EMPTY_CELLS: [char; 2] = ['.', '·'];
+
+/// Characters that can't be piece names, since they have a different meaning in board files,
+/// piece orientations (`A[03]`) or JSON output.
+const RESERVED_NAMES: [char; 6] = ['#', '[', ']', ';', '"', '\\'];
+
+/// The 12 pentominoes (see `PieceSet::pentominoes`).
+const PENTOMINOES: &str = "\
+.FF  I  L.  .N  PP  TTT
+FF.  I  L.  .N  PP  .T.
+.F.  I  L.  NN  P.  .T.
+     I  LL  N.
+     I
+
+U.U  V..  W..  .X.  .Y  ZZ.
+UUU  V..  WW.  XXX  YY  .Z.
+     VVV  .WW  .X.  .Y  .ZZ
+                    .Y";
+
+/// The pieces boards are filled with, keyed by their names.
+///
+/// Every piece is used exactly once in a solution. Pieces are tried in name order (see
+/// `Placements`). Every board type is filled with its own set (see `BoardType::pieces`).
+pub struct PieceSet {
+    pieces: HashMap<char, Piece>,
+    /// The piece names, sorted.
+    names: Vec<char>,
+}
+
+impl PieceSet {
+    pub fn new(pieces: HashMap<char, Piece>) -> PieceSet {
+        let mut names: Vec<char> = pieces.keys().copied().collect();
+        names.sort();
+        PieceSet { pieces, names }
+    }
 
-/// Holds a static map of all possible Kanoodle pieces keyed by
-/// their letter names.
-pub static PIECES: Lazy<HashMap<char, Piece>> = Lazy::new(|| {
-    let mut pieces = HashMap::new();
+    /// The 12 Kanoodle pieces (`A` - `L`).
+    pub fn kanoodle() -> &'static Arc<PieceSet> {
+        &KANOODLE
+    }
 
-    pieces.insert(
-        'A',
-        Piece::parse(
-            "A\n\
-               AAA",
+    /// The 12 pentominoes, named after the letters they look like.
+    pub fn pentominoes() -> &'static Arc<PieceSet> {
+        &PENTOMINO_SET
+    }
+
+    fn draw_kanoodle() -> PieceSet {
+        let mut pieces = HashMap::new();
+
+        pieces.insert(
             'A',
-        ),
-    );
-
-    pieces.insert(
-        'B',
-        Piece::parse(
-            "BB\n\
-               BBB",
+            Piece::parse(
+                "A\n\
+                   AAA",
+                'A',
+            ),
+        );
+
+        pieces.insert(
             'B',
-        ),
-    );
-
-    pieces.insert(
-        'C',
-        Piece::parse(
-            ".C\n\
-               .C\n\
-               .C\n\
-               CC",
+            Piece::parse(
+                "BB\n\
+                   BBB",
+                'B',
+            ),
+        );
+
+        pieces.insert(
             'C',
-        ),
-    );
-
-    pieces.insert(
-        'D',
-        Piece::parse(
-            "DDDD\n\
-               ..D",
+            Piece::parse(
+                ".C\n\
+                   .C\n\
+                   .C\n\
+                   CC",
+                'C',
+            ),
+        );
+
+        pieces.insert(
             'D',
-        ),
-    );
-
-    pieces.insert(
-        'E',
-        Piece::parse(
-            "EE\n\
-               .EEE",
+            Piece::parse(
+                "DDDD\n\
+                   ..D",
+                'D',
+            ),
+        );
+
+        pieces.insert(
             'E',
-        ),
-    );
-
-    pieces.insert(
-        'F',
-        Piece::parse(
-            "F\n\
-               FF",
+            Piece::parse(
+                "EE\n\
+                   .EEE",
+                'E',
+            ),
+        );
+
+        pieces.insert(
             'F',
-        ),
-    );
-
-    pieces.insert(
-        'G',
-        Piece::parse(
-            "GGG\n\
-               ..G\n\
-               ..G",
+            Piece::parse(
+                "F\n\
+                   FF",
+                'F',
+            ),
+        );
+
+        pieces.insert(
             'G',
-        ),
-    );
-
-    pieces.insert(
-        'H',
-        Piece::parse(
-            "HH\n\
-               .HH\n\
-               ..H",
+            Piece::parse(
+                "GGG\n\
+                   ..G\n\
+                   ..G",
+                'G',
+            ),
+        );
+
+        pieces.insert(
             'H',
-        ),
-    );
-
-    pieces.insert(
-        'I',
-        Piece::parse(
-            "II\n\
-               .I\n\
-               II",
+            Piece::parse(
+                "HH\n\
+                   .HH\n\
+                   ..H",
+                'H',
+            ),
+        );
+
+        pieces.insert(
             'I',
-        ),
-    );
+            Piece::parse(
+                "II\n\
+                   .I\n\
+                   II",
+                'I',
+            ),
+        );
 
-    pieces.insert('J', Piece::parse("JJJJ", 'J'));
+        pieces.insert('J', Piece::parse("JJJJ", 'J'));
 
-    pieces.insert(
-        'K',
-        Piece::parse(
-            "KK\n\
-               KK",
+        pieces.insert(
             'K',
-        ),
-    );
-
-    pieces.insert(
-        'L',
-        Piece::parse(
-            ".L\n\
-               LLL\n\
-               .L",
+            Piece::parse(
+                "KK\n\
+                   KK",
+                'K',
+            ),
+        );
+
+        pieces.insert(
             'L',
-        ),
-    );
+            Piece::parse(
+                ".L\n\
+                   LLL\n\
+                   .L",
+                'L',
+            ),
+        );
+
+        PieceSet::new(pieces)
+    }
+
+    /// Reads a piece set in the format `Piece::parse` reads a piece: every piece is drawn with its
+    /// name in its default orientation, and `.` (or a space) is an empty cell. Any number of pieces
+    /// may be drawn side by side or separated by empty lines, and any letter or symbol can be a
+    /// name (other than `#`, `[`, `]`, `;`, `"` and `\`):
+    ///
+    /// ```text
+    /// ..X..  II  ++
+    /// .XXX.  I   ++
+    /// ..X..  II  +
+    /// ```
+    ///
+    /// Pieces may have any number of cells, as long as every cell touches another cell of the
+    /// piece. The remaining orientations are derived from the drawing (see `Piece::parse`). Errors
+    /// name the line and column (both counted from 1).
+    pub fn parse(input: &str) -> Result<PieceSet, Error> {
+        let invalid = |line: usize, column: usize, message: String| Error::InvalidPieceFile {
+            line,
+            column,
+            message,
+        };
+
+        let input = input.replace("\r\n", "\n");
+        // The cells (rows and columns) of every piece, in the order they are read.
+        let mut found: HashMap<char, Vec<(usize, usize)>> = HashMap::new();
+        for (row, text) in input.split('\n').enumerate() {
+            for (col, ch) in text.chars().enumerate() {
+                if ch.is_whitespace() || EMPTY_CELLS.contains(&ch) {
+                    continue;
+                }
+                if ch.is_control() || RESERVED_NAMES.contains(&ch) {
+                    return Result::Err(invalid(
+                        row + 1,
+                        col + 1,
+                        format!("{} can't be the name of a piece", ch),
+                    ));
+                }
+                found.entry(ch).or_default().push((row, col));
+            }
+        }
+
+        // Pieces are checked in the order they are first read, so errors point at the first one.
+        let mut found: Vec<(char, Vec<(usize, usize)>)> = found.into_iter().collect();
+        found.sort_by_key(|(_, cells)| cells[0]);
+
+        let mut pieces = HashMap::new();
+        for (name, cells) in found {
+            if !is_connected(&cells) {
+                let (row, col) = cells[0];
+                return Result::Err(invalid(
+                    row + 1,
+                    col + 1,
+                    format!(
+                        "The cells of piece {} do not all touch each other (pieces are a single shape)",
+                        name
+                    ),
+                ));
+            }
+            pieces.insert(name, Piece::parse(&input, name));
+        }
+
+        if pieces.is_empty() {
+            return Result::Err(invalid(1, 1, "There are no pieces".to_string()));
+        }
+        Result::Ok(PieceSet::new(pieces))
+    }
+
+    pub fn get(&self, name: &char) -> Option<&Piece> {
+        self.pieces.get(name)
+    }
+
+    pub fn contains(&self, name: &char) -> bool {
+        self.pieces.contains_key(name)
+    }
+
+    /// The piece names, sorted.
+    pub fn names(&self) -> &[char] {
+        &self.names
+    }
+
+    /// The pieces, in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (char, &Piece)> {
+        self.names.iter().map(|name| (*name, &self.pieces[name]))
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The number of cells of all the pieces together, which is the number of cells a board
+    /// needs to have for the pieces to fill it.
+    pub fn cell_count(&self) -> usize {
+        self.pieces
+            .values()
+            .map(|piece| piece.orientations[0].cell_count())
+            .sum()
+    }
+
+    /// Describes the piece names for error messages, such as `A - L` (for a range of letters) or
+    /// `F, I, L`.
+    pub fn describe_names(&self) -> String {
+        let is_range = self
+            .names
+            .windows(2)
+            .all(|pair| pair[1] as u32 == pair[0] as u32 + 1);
+        match (self.names.first(), self.names.last()) {
+            (Option::Some(first), Option::Some(last)) if is_range && self.names.len() > 2 => {
+                format!("{} - {}", first, last)
+            }
+            _ => self
+                .names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+        }
+    }
+}
+
+/// Determines if every cell can be reached from the first one by moving up, down, left or right
+/// through the cells.
+fn is_connected(cells: &[(usize, usize)]) -> bool {
+    let mut reached = vec![cells[0]];
+    let mut next = 0;
+    while next < reached.len() {
+        let (row, col) = reached[next];
+        for cell in cells {
+            let touches = row.abs_diff(cell.0) + col.abs_diff(cell.1) == 1;
+            if touches && !reached.contains(cell) {
+                reached.push(*cell);
+            }
+        }
+        next += 1;
+    }
+    reached.len() == cells.len()
+}
+
+impl Index<&char> for PieceSet {
+    type Output = Piece;
+
+    fn index(&self, name: &char) -> &Piece {
+        &self.pieces[name]
+    }
+}
+
+/// The Kanoodle pieces (see `PieceSet::kanoodle`).
+static KANOODLE: Lazy<Arc<PieceSet>> = Lazy::new(|| Arc::new(PieceSet::draw_kanoodle()));
 
-    pieces
+/// The pentominoes (see `PieceSet::pentominoes`).
+static PENTOMINO_SET: Lazy<Arc<PieceSet>> = Lazy::new(|| {
+    Arc::new(PieceSet::parse(PENTOMINOES).expect("The pentominoes are a valid piece set"))
 });
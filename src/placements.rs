@@ -1,22 +1,31 @@
-use crate::pieces::{Shape, PIECES};
-use std::collections::HashSet;
+use crate::board::BoardType;
+use crate::error::Error;
+use crate::pieces::{PieceSet, Shape};
+use crate::random::Random;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
+use std::sync::Arc;
 
 /// A suggested piece to be placed or removed from the board.
 /// There is no information about where on the board the piece
 /// should be placed and a suggested piece is not guaranteed
 /// to be placeable.
 ///
-/// The wrapped `Shape` is in the orientation in which the piece
-/// is expected to be placed.
+/// The orientation index is the orientation (see `Piece::orientations`)
+/// in which the piece is expected to be placed.
 #[derive(Clone)]
 pub struct PieceSuggestion {
     pub name: char,
-    pub shape: &'static Shape,
     orientation_index: usize,
 }
 
+impl PieceSuggestion {
+    pub fn orientation_index(&self) -> usize {
+        self.orientation_index
+    }
+}
+
 pub struct PieceAfterSuccess {
     pub piece: PieceSuggestion,
 }
@@ -35,15 +44,69 @@ pub struct PieceAfterFailure {
 /// (all pieces placed previous to the suggestion) that suggestion will never be made
 /// again.
 ///
-/// Suggested pieces are returned in ascending order by piece name and orientation.
+/// Suggested pieces are returned in ascending order by piece name and orientation, unless a
+/// different order is set (see `set_search_order`).
+#[derive(Clone)]
 pub struct Placements {
+    pieces: Arc<PieceSet>,
     positions: Vec<PieceSuggestion>,
     used_letters: HashSet<char>,
     allow_3d_orientations: bool,
-    prevent_backtracking_beyond_piece: char,
+    prevent_backtracking_beyond_piece: Option<char>,
+    /// `None` is the lexical order, which is the common case and doesn't need any lookups.
+    order: Option<Arc<SearchOrder>>,
 }
 
-#[derive(Debug)]
+/// The order pieces (and the orientations of every piece) are suggested in by `Placements`,
+/// instead of the lexical order.
+///
+/// Every position still gets every piece and orientation suggested exactly once, so the same
+/// solutions are found in any order. Only the order they are found in changes.
+#[derive(Clone, Debug)]
+pub struct SearchOrder {
+    /// Piece names, in the order they are suggested.
+    pieces: Vec<char>,
+    /// The orientation indexes of every piece, in the order they are suggested.
+    orientations: HashMap<char, Vec<usize>>,
+}
+
+impl SearchOrder {
+    /// Shuffles the pieces of the set, and the orientations of every piece. The same random
+    /// numbers always give the same order, so a search in a random order can be repeated with
+    /// the same seed.
+    pub fn random(piece_set: &PieceSet, random: &mut Random) -> SearchOrder {
+        // The pieces are sorted first, since the order of a map's keys isn't the same every time.
+        let mut pieces: Vec<char> = piece_set.names().to_vec();
+        random.shuffle(&mut pieces);
+
+        let orientations = pieces
+            .iter()
+            .map(|name| {
+                let mut indexes: Vec<usize> = (0..piece_set[name].orientations.len()).collect();
+                random.shuffle(&mut indexes);
+                (*name, indexes)
+            })
+            .collect();
+
+        SearchOrder {
+            pieces,
+            orientations,
+        }
+    }
+
+    /// The pieces in the order they are suggested.
+    pub fn pieces(&self) -> &[char] {
+        &self.pieces
+    }
+
+    fn orientations(&self, name: char) -> &[usize] {
+        self.orientations.get(&name).map_or(&[], |indexes| indexes)
+    }
+}
+
+/// A piece and orientation. They are ordered by name and then orientation, the same order the
+/// solver tries them in.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct RequestedPiece {
     pub name: char,
     pub orientation_index: usize,
@@ -51,18 +114,29 @@ pub struct RequestedPiece {
 
 // Needed for StructOpt
 impl FromStr for RequestedPiece {
-    type Err = String;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let chars = &mut s.chars();
-        let name = chars.nth(0).unwrap();
-        let first_digit = chars.nth(1).unwrap().to_digit(10).unwrap();
-        let second_digit = chars.nth(0).unwrap().to_digit(10).unwrap();
+        let name = chars.next();
+        let first_digit = chars.nth(1).and_then(|c| c.to_digit(10));
+        let second_digit = chars.next().and_then(|c| c.to_digit(10));
 
-        return Result::Ok(RequestedPiece {
-            name,
-            orientation_index: ((first_digit * 10) + second_digit) as usize,
-        });
+        match (name, first_digit, second_digit) {
+            (Option::Some(name), Option::Some(first_digit), Option::Some(second_digit)) => {
+                Result::Ok(RequestedPiece {
+                    name,
+                    orientation_index: ((first_digit * 10) + second_digit) as usize,
+                })
+            }
+            _ => Result::Err(Error::InvalidOrientationSyntax(s.to_string())),
+        }
+    }
+}
+
+impl Display for RequestedPiece {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{:02}]", self.name, self.orientation_index)
     }
 }
 
@@ -76,72 +150,128 @@ impl Display for Placements {
 }
 
 impl Placements {
-    pub fn new(allow_3d_orientations: bool) -> Placements {
+    /// Creates placements for the pieces of the board type, with no pieces placed.
+    pub fn new(board_type: &BoardType) -> Placements {
         Placements {
+            pieces: Arc::clone(board_type.pieces()),
             positions: Vec::new(),
             used_letters: HashSet::new(),
-            allow_3d_orientations,
-            prevent_backtracking_beyond_piece: '?',
+            allow_3d_orientations: board_type.allows_3d_orientations(),
+            prevent_backtracking_beyond_piece: Option::None,
+            order: Option::None,
         }
     }
 
-    /// Initializes the state and provides the initial piece suggestions based on the
-    /// `start_at` pieces.
-    pub fn initialize(&mut self, start_at: Vec<RequestedPiece>) -> Vec<PieceSuggestion> {
-        if start_at.is_empty() {
-            panic!("Placements must be initialized with at least one starting piece");
+    /// Suggests pieces and orientations in this order from now on. Pieces that were already
+    /// suggested in the current position are not taken into account, so the order should be set
+    /// before the first suggestion is requested (see `first_suggestion`).
+    pub fn set_search_order(&mut self, order: Arc<SearchOrder>) {
+        self.order = Option::Some(order);
+    }
+
+    /// The order pieces are suggested in, or `None` for the lexical order.
+    pub fn search_order(&self) -> Option<&Arc<SearchOrder>> {
+        self.order.as_ref()
+    }
+
+    /// The first piece to try in the current position (the next position after the placed
+    /// pieces).
+    pub fn first_suggestion(&self) -> Option<PieceSuggestion> {
+        self.get_next_piece_to_try(Option::None)
+    }
+
+    /// Creates placements that have every piece in the path placed, in order. This is the state
+    /// the placements are in when a solver finds a solution with the path.
+    pub fn with_path(
+        board_type: &BoardType,
+        path: Vec<RequestedPiece>,
+    ) -> Result<Placements, Error> {
+        let mut placements = Placements::new(board_type);
+        if let Option::Some(last) = placements.initialize(path)?.pop() {
+            placements.get_next_piece_to_try_after_success(last);
         }
+        Result::Ok(placements)
+    }
 
-        let suggestions: Vec<PieceSuggestion> = start_at
-            .iter()
-            .map(|requested| PieceSuggestion {
+    /// Initializes the state and provides the initial piece suggestions based on the
+    /// `start_at` pieces.
+    ///
+    /// Fails without changing the state if a piece or orientation doesn't exist, or if a piece
+    /// is requested more than once (or is already placed).
+    pub fn initialize(
+        &mut self,
+        start_at: Vec<RequestedPiece>,
+    ) -> Result<Vec<PieceSuggestion>, Error> {
+        let mut suggestions = Vec::with_capacity(start_at.len());
+        let mut letters = self.used_letters.clone();
+        for requested in start_at {
+            let piece = self
+                .pieces
+                .get(&requested.name)
+                .ok_or(Error::UnknownPiece(requested.name))?;
+            if requested.orientation_index >= piece.orientations.len() {
+                return Result::Err(Error::UnknownOrientation(requested));
+            }
+            if !letters.insert(requested.name) {
+                return Result::Err(Error::DuplicatePiece(requested.name));
+            }
+            suggestions.push(PieceSuggestion {
                 name: requested.name,
                 orientation_index: requested.orientation_index,
-                shape: get_piece_orientation(requested.name, requested.orientation_index).unwrap(),
-            })
-            .collect();
+            });
+        }
 
         // The last piece cannot be added to the placement state. It is up to the caller
         // to validate it and commit it if it is valid.
-        let suggestions_to_track = suggestions.len() - 1;
-
-        for i in 0..suggestions_to_track {
-            let suggestion = suggestions.get(i).unwrap();
-            if !self.used_letters.insert(suggestion.name) {
-                panic!(
-                    "Initial placements contain duplicated piece {}",
-                    suggestion.name
-                );
-            }
+        let suggestions_to_track = suggestions.len().saturating_sub(1);
+        for suggestion in &suggestions[..suggestions_to_track] {
+            self.used_letters.insert(suggestion.name);
             self.positions.push(suggestion.clone());
         }
 
-        // We also need to check the last piece that we didn't push into the iterator
-        // is not already placed.
-        let last_piece = suggestions.last().unwrap();
-        if self.used_letters.contains(&last_piece.name) {
-            panic!(
-                "Initial placements contain duplicated piece {}",
-                last_piece.name
-            );
-        }
+        Result::Ok(suggestions)
+    }
 
-        return suggestions;
+    /// The number of placed pieces.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// The placed pieces and their orientations, in the order they were placed.
+    pub fn path(&self) -> Vec<RequestedPiece> {
+        self.positions
+            .iter()
+            .map(|position| RequestedPiece {
+                name: position.name,
+                orientation_index: position.orientation_index,
+            })
+            .collect()
+    }
+
+    /// The piece the placements will never backtrack beyond (see
+    /// `prevent_backtracking_beyond_this_piece`), if there is one.
+    pub fn backtracking_fence(&self) -> Option<char> {
+        self.prevent_backtracking_beyond_piece
     }
 
     pub fn prevent_backtracking_beyond_this_piece(&mut self, piece: Option<char>) {
         match piece {
-            Option::Some(name) => self.prevent_backtracking_beyond_piece = name,
-            Option::None => match self.positions.last() {
-                Option::Some(p) => self.prevent_backtracking_beyond_piece = p.name,
-                _ => {}
-            },
+            Option::Some(name) => self.prevent_backtracking_beyond_piece = Option::Some(name),
+            Option::None => {
+                if let Option::Some(p) = self.positions.last() {
+                    self.prevent_backtracking_beyond_piece = Option::Some(p.name)
+                }
+            }
         }
     }
 
     /// Removes the last placed piece (if there was one) and returns it.
     ///
-    /// Normally, if all 12 pieces are in use, that means a particular solution has been found,
+    /// Normally, if all pieces are in use, that means a particular solution has been found,
     /// but that doesn't mean that all possible solutions have been found.
     ///
     /// This allows the caller to remove the last piece in order to get a new suggestion for the
@@ -149,7 +279,7 @@ impl Placements {
     pub fn remove_last_piece(&mut self) -> Option<PieceSuggestion> {
         match self.positions.pop() {
             Option::Some(previous) => {
-                if self.prevent_backtracking_beyond_piece == previous.name {
+                if self.prevent_backtracking_beyond_piece == Option::Some(previous.name) {
                     // We're done here. The iterator was configured to stop
                     // if this piece got removed.
                     Option::None
@@ -174,9 +304,8 @@ impl Placements {
         self.used_letters.insert(success.name);
         self.positions.push(success);
 
-        return self
-            .get_next_piece_to_try((('A' as u8) - 1) as char)
-            .map(|piece| PieceAfterSuccess { piece });
+        self.get_next_piece_to_try(Option::None)
+            .map(|piece| PieceAfterSuccess { piece })
     }
 
     /// If a suggested piece could not be placed, this method should be called when requesting
@@ -196,27 +325,17 @@ impl Placements {
         failure: PieceSuggestion,
     ) -> Option<PieceAfterFailure> {
         // First we'll try a different orientation for the failed piece (if there is one).
-        let next_index = failure.orientation_index + 1;
-        let next_orientation = get_piece_orientation(failure.name, next_index);
-
-        if next_orientation.is_some() {
-            let next = next_orientation.unwrap();
-            if !next.is_3d || self.allow_3d_orientations {
-                // We found another orientation for the piece, so let's return it to be tried.
-                return Option::Some(PieceAfterFailure {
-                    piece: PieceSuggestion {
-                        name: failure.name,
-                        orientation_index: next_index,
-                        shape: next,
-                    },
-                    to_remove: Vec::new(),
-                });
-            }
+        if let Option::Some(piece) = self.get_next_orientation_to_try(&failure) {
+            // We found another orientation for the piece, so let's return it to be tried.
+            return Option::Some(PieceAfterFailure {
+                piece,
+                to_remove: Vec::new(),
+            });
         }
 
         // There were no remaining orientations for the piece, therefore, lets try the next
         // available piece.
-        let next_piece = self.get_next_piece_to_try(failure.name);
+        let next_piece = self.get_next_piece_to_try(Option::Some(failure.name));
 
         if next_piece.is_some() {
             // We found another piece so return it to be tried.
@@ -229,15 +348,11 @@ impl Placements {
 
         // Ok, there are no remaining pieces to try. That means we need to try popping off the
         // previously successfully placed piece and then getting a suggestion.
-        let previous_success_opt = self.positions.pop();
-        if previous_success_opt.is_none() {
-            // We've popped everything off the queue and exhausted all possible suggestions.
-            return Option::None;
-        }
-
-        let previous_success = previous_success_opt.unwrap();
+        // If there is nothing to pop, we've popped everything off the queue and exhausted all
+        // possible suggestions.
+        let previous_success = self.positions.pop()?;
         self.used_letters.remove(&previous_success.name);
-        if previous_success.name == self.prevent_backtracking_beyond_piece {
+        if self.prevent_backtracking_beyond_piece == Option::Some(previous_success.name) {
             // The iterator is configured to stop when this piece is removed
             // So we're done here.
             return Option::None;
@@ -278,23 +393,75 @@ impl Placements {
         }
     }
 
-    fn get_next_piece_to_try(&self, after_char: char) -> Option<PieceSuggestion> {
-        let mut next_name = (after_char as u8 + 1) as char;
-        while next_name < 'M' && self.used_letters.contains(&next_name) {
-            next_name = ((next_name as u8) + 1) as char;
-        }
+    /// The next piece that isn't placed yet, after the `after` piece (or the first one), in its
+    /// first orientation that can be used on the board.
+    fn get_next_piece_to_try(&self, after: Option<char>) -> Option<PieceSuggestion> {
+        let order = match &self.order {
+            Option::Some(order) => order,
+            Option::None => {
+                let names = self.pieces.names();
+                let start = after.map_or(0, |after| {
+                    names
+                        .iter()
+                        .position(|name| *name == after)
+                        .map_or(names.len(), |index| index + 1)
+                });
+                // The flat orientations come first, so a piece whose first orientation can't be
+                // used on the board has no orientation that can.
+                return names[start..]
+                    .iter()
+                    .filter(|name| !self.used_letters.contains(name))
+                    .find_map(|name| self.get_allowed_orientation(*name, 0));
+            }
+        };
 
-        return get_piece_orientation(next_name, 0).map(|shape| PieceSuggestion {
-            name: next_name,
-            orientation_index: 0,
-            shape,
+        let start = after.map_or(0, |after| {
+            order
+                .pieces
+                .iter()
+                .position(|name| *name == after)
+                .map_or(order.pieces.len(), |rank| rank + 1)
         });
+        order.pieces[start..]
+            .iter()
+            .filter(|name| !self.used_letters.contains(name))
+            .find_map(|name| {
+                order
+                    .orientations(*name)
+                    .iter()
+                    .find_map(|index| self.get_allowed_orientation(*name, *index))
+            })
+    }
+
+    /// The orientation of the piece that comes after the given one, if there is one that can be
+    /// used on the board.
+    fn get_next_orientation_to_try(&self, piece: &PieceSuggestion) -> Option<PieceSuggestion> {
+        match &self.order {
+            // The flat orientations come before the 3D ones, so there is nothing left to try
+            // once a 3D orientation is not allowed.
+            Option::None => self.get_allowed_orientation(piece.name, piece.orientation_index + 1),
+            Option::Some(order) => order
+                .orientations(piece.name)
+                .iter()
+                .skip_while(|index| **index != piece.orientation_index)
+                .skip(1)
+                .find_map(|index| self.get_allowed_orientation(piece.name, *index)),
+        }
     }
-}
 
-fn get_piece_orientation(piece_name: char, orientation_index: usize) -> Option<&'static Shape> {
-    match (*PIECES).get(&piece_name) {
-        Option::None => Option::None,
-        Option::Some(piece) => piece.orientations.get(orientation_index),
+    fn get_allowed_orientation(&self, name: char, index: usize) -> Option<PieceSuggestion> {
+        self.get_piece_orientation(name, index)
+            .filter(|shape| !shape.is_3d || self.allow_3d_orientations)
+            .map(|_| PieceSuggestion {
+                name,
+                orientation_index: index,
+            })
+    }
+
+    fn get_piece_orientation(&self, piece_name: char, orientation_index: usize) -> Option<&Shape> {
+        match self.pieces.get(&piece_name) {
+            Option::None => Option::None,
+            Option::Some(piece) => piece.orientations.get(orientation_index),
+        }
     }
 }
@@ -0,0 +1,83 @@
+use crate::placements::RequestedPiece;
+use crate::solver::Solver;
+
+/// Estimates how much of a depth-first search is done.
+///
+/// Pieces are tried in lexical order, so where the first two pieces placed beyond the initial
+/// state sit among every piece (and orientation) that fits there tells roughly how far along the
+/// search is. Every first piece is assumed to take the same amount of time to search, and so is
+/// every second piece that fits after it. That is rarely true, but it is enough to tell whether a
+/// search is moving and roughly how much of it is left.
+pub struct ProgressEstimate {
+    /// The pieces of the initial state, as they start every path.
+    initial_path: Vec<RequestedPiece>,
+    /// Every first piece that fits beyond the initial state along with every second piece that
+    /// fits after it, in the order they are searched.
+    first_pieces: Vec<(RequestedPiece, Vec<RequestedPiece>)>,
+}
+
+impl ProgressEstimate {
+    /// Lists the first two pieces of every subtree of the search, starting from the solver's
+    /// initial state. (The solver may have already started searching).
+    pub fn new(solver: &Solver) -> ProgressEstimate {
+        let restarted = solver.restart();
+        let initial_pieces = restarted.path().len();
+
+        let mut initial_path = Vec::new();
+        let mut first_pieces: Vec<(RequestedPiece, Vec<RequestedPiece>)> = Vec::new();
+        for subtree in restarted.split(2) {
+            let mut path = subtree.path();
+            if path.len() <= initial_pieces {
+                // The initial state is a solution.
+                continue;
+            }
+            let mut beyond = path.split_off(initial_pieces).into_iter();
+            initial_path = path;
+            let first = beyond.next().unwrap();
+            match first_pieces.last_mut() {
+                Option::Some((piece, seconds)) if *piece == first => seconds.extend(beyond),
+                _ => first_pieces.push((first, beyond.collect())),
+            }
+        }
+
+        ProgressEstimate {
+            initial_path,
+            first_pieces,
+        }
+    }
+
+    /// The number of pieces in the initial state.
+    pub fn initial_pieces(&self) -> usize {
+        self.initial_path.len()
+    }
+
+    /// The estimated fraction (from 0 to 1) of the search that is done once the search reaches
+    /// the path. `None` if the path is not part of the search below the initial state (such as
+    /// after backtracking removed a piece of the initial state).
+    pub fn fraction_done(&self, path: &[RequestedPiece]) -> Option<f64> {
+        let initial_pieces = self.initial_path.len();
+        if self.first_pieces.is_empty()
+            || path.len() <= initial_pieces
+            || path[..initial_pieces] != self.initial_path[..]
+        {
+            return Option::None;
+        }
+
+        // Pieces that aren't listed are about to be rejected, so they count the same as the
+        // listed pieces that come after them.
+        let first = &path[initial_pieces];
+        let first_index = self
+            .first_pieces
+            .partition_point(|(piece, _)| piece < first);
+        let mut done = first_index as f64;
+        if let (Option::Some((piece, seconds)), Option::Some(second)) = (
+            self.first_pieces.get(first_index),
+            path.get(initial_pieces + 1),
+        ) {
+            if piece == first && !seconds.is_empty() {
+                done += seconds.partition_point(|p| p < second) as f64 / seconds.len() as f64;
+            }
+        }
+        Option::Some(done / self.first_pieces.len() as f64)
+    }
+}
@@ -0,0 +1,142 @@
+use crate::solver::Solver;
+use std::fmt::{Display, Formatter};
+
+/// How hard a starting position is to complete by hand. (See `Rating::score`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Starter,
+    Junior,
+    Expert,
+    Master,
+}
+
+impl Difficulty {
+    /// The difficulty of a score: Starter below 8, Junior below 16, Expert below 24 and Master
+    /// from there on. Generated challenges (see `generate::generate_challenge`) with 4 pieces
+    /// missing are usually Starters, and ones with 10 missing are Experts or Masters.
+    pub fn of(score: f64) -> Difficulty {
+        if score < 8.0 {
+            Difficulty::Starter
+        } else if score < 16.0 {
+            Difficulty::Junior
+        } else if score < 24.0 {
+            Difficulty::Expert
+        } else {
+            Difficulty::Master
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The statistics of a complete search from a starting position, and the difficulty score they
+/// add up to. (See `rate`)
+#[derive(Clone, Debug)]
+pub struct Rating {
+    pub solutions: u64,
+    /// The number of pieces tried before the first solution was found, or `None` if there are
+    /// no solutions.
+    pub nodes_to_first_solution: Option<u64>,
+    /// The number of pieces tried in the whole search.
+    pub nodes: u64,
+    /// The average number of pieces that fit in a position, by the number of pieces that were
+    /// already placed beyond the starting position.
+    pub branching: Vec<f64>,
+    /// The number of pieces that fit, but don't lead to any solution (including the ones removed
+    /// by dead region pruning). Every one of them is a wrong turn.
+    pub dead_ends: u64,
+}
+
+impl Rating {
+    /// The difficulty score. It adds up the ways a position is hard to complete by hand:
+    ///
+    /// * `log2` of the branching factor at every depth. This is the number of yes or no
+    ///   choices it takes to pick the right piece in every position (without knowing which
+    ///   ones lead to a solution), so it grows with the number of missing pieces.
+    /// * `log2(1 + dead ends per solution)`, for the wrong turns that have to be taken back.
+    /// * `log10(1 + pieces tried before the first solution)`, for how long it takes to find the
+    ///   first solution by trying pieces in order.
+    ///
+    /// Positions without a solution have no score.
+    pub fn score(&self) -> Option<f64> {
+        let nodes_to_first_solution = self.nodes_to_first_solution?;
+        let choices: f64 = self
+            .branching
+            .iter()
+            .map(|branching| branching.max(1.0).log2())
+            .sum();
+        let wrong_turns = (1.0 + self.dead_ends as f64 / self.solutions as f64).log2();
+        let effort = (1.0 + nodes_to_first_solution as f64).log10();
+        Option::Some(choices + wrong_turns + effort)
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.score().map(Difficulty::of)
+    }
+}
+
+/// Searches every solution from the solver's position and rates how difficult the position is.
+/// The whole search has to be done, so this is only practical for positions that have a few
+/// pieces missing.
+pub fn rate(solver: Solver) -> Rating {
+    let mut solver = solver.with_profile();
+    let stats = solver.stats();
+    let nodes_before = stats.nodes();
+    let initial_pieces = solver.path().len();
+
+    let mut solutions = 0u64;
+    let mut nodes_to_first_solution = Option::None;
+    // The pieces on the path to at least one solution. Solutions are found in lexical order, so
+    // the pieces a solution shares with the previous one were already counted.
+    let mut solution_pieces = 0u64;
+    // The number of solutions that were completed by a piece at every depth. Those pieces don't
+    // have a next position to place pieces in.
+    let mut completed = Vec::new();
+    let mut previous = Vec::new();
+    for solution in solver.by_ref() {
+        solutions += 1;
+        nodes_to_first_solution.get_or_insert(stats.nodes() - nodes_before);
+
+        let path = solution.placements.path();
+        let shared = path
+            .iter()
+            .zip(&previous)
+            .take_while(|(piece, other)| piece == other)
+            .count()
+            .max(initial_pieces);
+        solution_pieces += (path.len() - shared) as u64;
+        if let Option::Some(depth) = path.len().checked_sub(initial_pieces + 1) {
+            if completed.len() <= depth {
+                completed.resize(depth + 1, 0u64);
+            }
+            completed[depth] += 1;
+        }
+        previous = path;
+    }
+
+    let profile = solver.profile().cloned().unwrap_or_default();
+    let placed = profile.placed();
+    // Every placed piece that doesn't complete the board leads to a position at the next depth.
+    let branching = (0..placed.len())
+        .map(|depth| {
+            let positions = if depth == 0 {
+                1
+            } else {
+                placed[depth - 1] - completed.get(depth - 1).copied().unwrap_or(0)
+            };
+            placed[depth] as f64 / positions.max(1) as f64
+        })
+        .collect();
+
+    Rating {
+        solutions,
+        nodes_to_first_solution,
+        nodes: stats.nodes() - nodes_before,
+        branching,
+        dead_ends: placed.iter().sum::<u64>() - solution_pieces + profile.pruned(),
+    }
+}
@@ -1,114 +1,699 @@
-use crate::board::{create_board, BoardType, Variation};
+use crate::board::{Board, BoardType};
+use crate::checkpoint::Checkpoint;
+use crate::error::Error;
 use crate::layer::Position;
-use crate::pieces::{Piece, Shape};
-use crate::placements::RequestedPiece;
-use crate::{placements, PieceSuggestion, Placements, PIECES};
+use crate::pieces::{PieceSet, Shape};
+use crate::placements::{RequestedPiece, SearchOrder};
+use crate::symmetry::Symmetry;
+use crate::{placements, PieceSuggestion, Placements};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A solution found by the `Solver`.
+pub struct Solution {
+    /// The placement path that led to the solution. It displays as `A[00]; B[03]; ...`
+    pub placements: Placements,
+    /// A snapshot of the filled board.
+    pub board: Board,
+}
 
-/// Finds solutions between the initial_state and the ending_at path (exclusive)
+/// A stop condition for the `Solver`. Only solutions whose placement paths are lexically less
+/// than or equal to the ending path are allowed.
 ///
-/// The algorithm used here is a naive depth-first search for solutions. Starting at the
-/// initial piece and orientation, all combinations of other pieces and orientations are
-/// tried. The pieces and orientations are always tried in lexical order. If a piece cannot
-/// be placed on the board, that tree will be abandoned.
+/// Since solutions are found in lexical order, it is meant to be used with `take_while`:
 ///
-/// Pieces will always be placed in the top-most, left-most available space on the board
-/// (except when added as part of the initial state).
+/// ```no_run
+/// # use kanoodle_solver::{BoardType, RequestedPiece, Solver};
+/// # use kanoodle_solver::solver::EndingAt;
+/// let ending_at = EndingAt::new(vec!["B[00]".parse::<RequestedPiece>().unwrap()]);
+/// let solver = Solver::new(&BoardType::Rectangle, None, false).unwrap();
+/// for solution in solver.take_while(|s| ending_at.allows(s)) {
+///     println!("{}", solution.placements);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct EndingAt {
+    path: Vec<RequestedPiece>,
+}
+
+impl EndingAt {
+    pub fn new(pieces: Vec<RequestedPiece>) -> EndingAt {
+        EndingAt { path: pieces }
+    }
+
+    /// Compares the paths piece by piece, which is the same as comparing their path strings
+    /// (`A[00]; B[03]; ...`) without having to format them.
+    pub fn allows(&self, solution: &Solution) -> bool {
+        solution.placements.path() <= self.path
+    }
+}
+
+impl Display for EndingAt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            convert_requested_pieces_to_path_string(self.path.clone())
+        )
+    }
+}
+
+/// The search engine used to find solutions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Engine {
+    /// The depth-first search implemented by `Solver`. Solutions are found in lexical order.
+    Dfs,
+    /// The exact cover search implemented by `DlxSolver`.
+    Dlx,
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("dfs") {
+            Result::Ok(Engine::Dfs)
+        } else if s.eq_ignore_ascii_case("dlx") {
+            Result::Ok(Engine::Dlx)
+        } else {
+            Result::Err(format!("Unknown engine {} (expected dfs or dlx)", s))
+        }
+    }
+}
+
+/// Counts how much work a search has done so far.
 ///
-/// A single iteration of placing a piece looks like the following:
-/// 1. Attempt to place piece 'X' in orientation [n] into next board position
-/// 2. If the piece fits:
-///   1. If the board is solved:
-///     1. If the solution falls outside the ending_at path, we're done. exit.
-///     2. Otherwise, Print out the solution
-///     2. Remove the last piece and pretend as if the last piece failed to be placed
-///   2. If the board is not solved:
-///     1. Get the next piece to try to place and goto 1.
-/// 3. If the piece does not fit:
-///   1. Ask for a new piece to try (this new piece and orientation will always be 'after'
-///      the failed piece).
-///   2. If all possible pieces have already been tried at this position, one or more pieces
-///      will be removed until we find another piece that can be tried.
-///     2. Goto with the new piece to try.
-///   3. Otherwise, goto 1 with the new piece to try.
-pub fn find_solutions(
-    board_type: Option<BoardType>,
-    initial_state: Option<Vec<String>>,
-    allow_backtracking: Option<bool>,
-    ending_at: Option<Vec<RequestedPiece>>,
-) {
-    let requested_board_type = board_type.unwrap_or(BoardType::Rectangle);
+/// Solvers that were split from the same solver (see `Solver::split`) share their stats, so the
+/// counts include the work done by every subtree, on every thread.
+#[derive(Default)]
+pub struct SearchStats {
+    nodes: AtomicU64,
+    saved: AtomicU64,
+}
+
+impl SearchStats {
+    /// The number of times a piece was tried in a position.
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    /// The number of nodes dead region pruning saved: the nodes a search without pruning would
+    /// have tried below the pieces that were removed because they left a dead region (see
+    /// `Board::has_dead_region`). `nodes + saved` is the number of nodes the same search takes
+    /// without pruning.
+    ///
+    /// Only counted by solvers that were asked to (see `Solver::with_saved_nodes_counted`).
+    pub fn saved(&self) -> u64 {
+        self.saved.load(Ordering::Relaxed)
+    }
+
+    fn add(&self, nodes: u64, saved: u64) {
+        self.nodes.fetch_add(nodes, Ordering::Relaxed);
+        self.saved.fetch_add(saved, Ordering::Relaxed);
+    }
+}
+
+/// The number of pieces tried and placed at every depth of a search. (See
+/// `Solver::with_profile`)
+#[derive(Clone, Debug, Default)]
+pub struct SearchProfile {
+    /// The number of pieces in the initial state. Depths are counted from there. (Pieces tried
+    /// after backtracking beyond the initial state are counted at depth 0).
+    initial_pieces: usize,
+    tried: Vec<u64>,
+    placed: Vec<u64>,
+    pruned: u64,
+}
+
+impl SearchProfile {
+    /// The number of times a piece was tried in a position, by the number of pieces that were
+    /// already placed beyond the initial state.
+    pub fn tried(&self) -> &[u64] {
+        &self.tried
+    }
+
+    /// The number of pieces that were placed (they fit and weren't rejected), by depth like
+    /// `tried`. Every placed piece is the root of a subtree of the search.
+    pub fn placed(&self) -> &[u64] {
+        &self.placed
+    }
+
+    /// The number of pieces that fit, but were removed by dead region pruning (see
+    /// `Solver::with_dead_region_pruning`). They are not counted as placed.
+    pub fn pruned(&self) -> u64 {
+        self.pruned
+    }
+
+    fn add(&mut self, depth: usize, placed: bool) {
+        if self.tried.len() <= depth {
+            self.tried.resize(depth + 1, 0);
+            self.placed.resize(depth + 1, 0);
+        }
+        self.tried[depth] += 1;
+        if placed {
+            self.placed[depth] += 1;
+        }
+    }
+}
+
+/// The result of searching for a limited number of nodes. (See `Solver::search`)
+// Steps are handled right away, so they are not worth boxing the solution for.
+#[allow(clippy::large_enum_variant)]
+pub enum SearchStep {
+    Found(Solution),
+    /// The search ran out of nodes before it found another solution.
+    Paused,
+    /// Every solution has been found.
+    Finished,
+}
+
+/// The reasons a piece that was suggested is not placed.
+enum Rejected {
+    DoesNotFit,
+    /// The symmetry does not allow the piece in the position.
+    Symmetric,
+    /// The piece leaves a dead region behind.
+    DeadRegion,
+}
+
+/// Finds solutions starting from an initial board state.
+///
+/// The solver is an iterator. Solutions are found lazily, one at a time, so callers decide
+/// what to do with them (print them, count them, stop early, etc.).
+#[derive(Clone)]
+pub struct Solver {
+    initial_board: Board,
+    board: Board,
+    placements: Placements,
+    next_piece: Option<PieceSuggestion>,
+    /// Set when the board is already solved, but the solution has not been returned yet. (e.g.
+    /// The initial state was a complete solution).
+    unreported_solution: bool,
+    /// When set, only canonical solutions are found. (See `Symmetry`)
+    symmetry: Option<Arc<Symmetry>>,
+    /// When set, pieces that leave a dead region behind are removed right away.
+    prune_dead_regions: bool,
+    /// When set, the subtree below every pruned piece is searched to count the nodes pruning
+    /// saved. (See `SearchStats::saved`)
+    count_saved_nodes: bool,
+    stats: Arc<SearchStats>,
+    /// Only kept when asked for, since it is updated for every node.
+    profile: Option<Box<SearchProfile>>,
+}
+
+impl Solver {
+    /// Creates a solver for the requested board type.
+    ///
+    /// The initial state is a vector of board layers (see `Shape::parse`). If it is `None` the
+    /// solver starts with an empty board. Unless backtracking is allowed, only solutions that
+    /// contain every piece of the initial state (as placed) are found.
+    ///
+    /// Fails if the pieces can't fill the board (see `BoardType::check_cell_count`), or if a
+    /// piece of the initial state is not on the board, is not one of the piece's orientations, or
+    /// overlaps another piece.
+    pub fn new(
+        board_type: &BoardType,
+        initial_state: Option<Vec<String>>,
+        allow_backtracking: bool,
+    ) -> Result<Solver, Error> {
+        let (board, placements, next_piece) =
+            initialize(initial_state, board_type, allow_backtracking)?;
+
+        let unreported_solution = next_piece.is_none() && board.solved();
+        Result::Ok(Solver {
+            initial_board: board.clone(),
+            board,
+            placements,
+            next_piece,
+            unreported_solution,
+            symmetry: Option::None,
+            prune_dead_regions: true,
+            count_saved_nodes: false,
+            stats: Arc::new(SearchStats::default()),
+            profile: Option::None,
+        })
+    }
+
+    /// Only finds the canonical solution of every class of symmetric solutions. The symmetry
+    /// must have been created from the initial board state and backtracking must not be allowed,
+    /// since removing pieces of the initial state would change which symmetries apply.
+    pub fn with_symmetry(mut self, symmetry: Arc<Symmetry>) -> Solver {
+        self.symmetry = Option::Some(symmetry);
+        self
+    }
+
+    /// Turns dead region pruning on or off. It is on by default.
+    ///
+    /// Normally a piece is only removed once nothing fits in the next position. With pruning, a
+    /// piece is removed right after it is placed if it leaves a dead region on the board (see
+    /// `Board::has_dead_region`), since the board can never be solved from there. The same
+    /// solutions are found either way.
+    pub fn with_dead_region_pruning(mut self, enabled: bool) -> Solver {
+        self.prune_dead_regions = enabled;
+        self
+    }
+
+    /// Counts the nodes dead region pruning saves (see `SearchStats::saved`). They are counted
+    /// by searching the subtree below every pruned piece without pruning, so the search takes
+    /// about as long as a search without pruning. It still finds the same solutions, in the same
+    /// order, as a search with pruning.
+    pub fn with_saved_nodes_counted(mut self) -> Solver {
+        self.count_saved_nodes = true;
+        self
+    }
+
+    /// Tries pieces and orientations in this order instead of the lexical one (see
+    /// `SearchOrder`). The same solutions are found, but in a different order, so a random order
+    /// finds a different first solution for every seed.
+    ///
+    /// Must be set before the search starts. Options that rely on the lexical order (such as
+    /// `EndingAt`, `ProgressEstimate` and checkpoints) don't apply to other orders.
+    pub fn with_search_order(mut self, order: Arc<SearchOrder>) -> Solver {
+        self.placements.set_search_order(order);
+        if self.next_piece.is_some() {
+            self.next_piece = self.placements.first_suggestion();
+        }
+        self
+    }
+
+    /// The work done by the solver (and every solver split from it) so far.
+    pub fn stats(&self) -> Arc<SearchStats> {
+        Arc::clone(&self.stats)
+    }
+
+    /// Keeps track of the pieces tried and placed at every depth (see `profile`). Unlike the
+    /// stats, a profile is not shared with solvers split from this one.
+    pub fn with_profile(mut self) -> Solver {
+        self.profile = Option::Some(Box::new(SearchProfile {
+            initial_pieces: self.placements.len(),
+            ..SearchProfile::default()
+        }));
+        self
+    }
 
-    println!("Finding solutions for {:?} board", requested_board_type);
+    /// The pieces tried and placed at every depth so far, if `with_profile` was used.
+    pub fn profile(&self) -> Option<&SearchProfile> {
+        self.profile.as_deref()
+    }
 
-    let (mut board, mut placements, mut next_piece) =
-        initialize(initial_state, &requested_board_type, allow_backtracking);
+    /// Continues a search from a checkpoint. Options that are not part of the search position
+    /// (such as symmetry or pruning) are not saved in checkpoints, so they must be set again.
+    pub fn resume(checkpoint: &Checkpoint) -> Result<Solver, Error> {
+        let (initial_board, _, _) = initialize(
+            Option::Some(checkpoint.initial_state.clone()),
+            &checkpoint.board_type,
+            false,
+        )?;
+        let invalid = |message: String| Error::InvalidCheckpoint(message);
+
+        // Every piece was added to the board with its top-most, left-most cell in the position
+        // the search (or the initial state) put it in, so that's where we add it back.
+        let mut board = Board::new(&checkpoint.board_type);
+        let pieces = Arc::clone(board.pieces());
+        for piece in &checkpoint.path {
+            let shape = get_piece_orientation_checked(&pieces, piece)
+                .ok_or_else(|| invalid(format!("Unknown piece {} in the checkpoint", piece)))?;
+            let position = parse_cell_positions(&checkpoint.state, &piece.name)
+                .first()
+                .copied()
+                .ok_or_else(|| {
+                    invalid(format!("Piece {} is not on the checkpoint board", piece))
+                })?;
+            board
+                .try_add_shape_at(shape, piece.name, &position)
+                .map_err(|_| {
+                    invalid(format!(
+                        "Piece {} does not fit on the checkpoint board",
+                        piece
+                    ))
+                })?;
+            if board.mask_of(piece.name).is_none_or(|mask| {
+                board.masks().positions_in(mask).ne(parse_cell_positions(
+                    &checkpoint.state,
+                    &piece.name,
+                )
+                .iter())
+            }) {
+                return Result::Err(invalid(format!(
+                    "Piece {} does not match the checkpoint board",
+                    piece
+                )));
+            }
+        }
 
-    let mut solutions = 0u32;
+        // The next position is normally the top-most, left-most empty cell, but not right after
+        // a piece of the initial state was removed.
+        if !board.positions().contains(&checkpoint.next_position) {
+            return Result::Err(invalid(
+                "The next position in the checkpoint is not on the board".to_string(),
+            ));
+        }
+        if board.letter_at(&checkpoint.next_position).is_some() {
+            return Result::Err(invalid(
+                "The next position in the checkpoint is not empty".to_string(),
+            ));
+        }
+        board.set_next_pos(checkpoint.next_position);
+
+        // `initialize` only keeps track of all but the last piece, which is the one it suggests.
+        let (placements, next_piece) = match &checkpoint.next {
+            Option::Some(next) => {
+                let mut placements = Placements::new(&checkpoint.board_type);
+                let mut path = checkpoint.path.clone();
+                path.push(next.clone());
+                let next_piece = placements.initialize(path)?.pop();
+                (placements, next_piece)
+            }
+            Option::None => (
+                Placements::with_path(&checkpoint.board_type, checkpoint.path.clone())?,
+                Option::None,
+            ),
+        };
+
+        let mut solver = Solver::new(&checkpoint.board_type, Option::None, false)?;
+        solver.initial_board = initial_board;
+        solver.board = board;
+        solver.placements = placements;
+        solver.next_piece = next_piece;
+        solver.unreported_solution = checkpoint.unreported_solution;
+        // `None` would fence in the last piece of the path, so the fence is only set if the
+        // search had one.
+        if checkpoint.fence.is_some() {
+            solver
+                .placements
+                .prevent_backtracking_beyond_this_piece(checkpoint.fence);
+        }
+        Result::Ok(solver)
+    }
 
-    let ending_at_placement = convert_requested_pieces_to_path_string(ending_at);
-    println!(
-        "Ending at {}",
-        if ending_at_placement.starts_with("Z") {
-            "NO-LIMIT".to_string()
+    /// Saves the position of the search. Resuming from the checkpoint continues exactly where
+    /// the search left off.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            board_type: self.board.board_type(),
+            initial_state: self.initial_board.state(),
+            state: self.board.state(),
+            path: self.placements.path(),
+            next: self.next_piece.as_ref().map(|piece| RequestedPiece {
+                name: piece.name,
+                orientation_index: piece.orientation_index(),
+            }),
+            next_position: *self.board.next_pos(),
+            unreported_solution: self.unreported_solution,
+            fence: self.placements.backtracking_fence(),
+            counts: Default::default(),
+        }
+    }
+
+    /// A solver with the same options that starts the search over from the initial state.
+    /// Backtracking beyond the initial state is not allowed.
+    pub(crate) fn restart(&self) -> Solver {
+        let initial_state = if self.initial_board.occupied() == 0 {
+            Option::None
         } else {
-            ending_at_placement.to_string()
+            Option::Some(self.initial_board.state())
+        };
+        let mut solver = Solver::new(&self.initial_board.board_type(), initial_state, false)
+            .expect("The initial state was already on the board");
+        solver.symmetry = self.symmetry.clone();
+        solver.prune_dead_regions = self.prune_dead_regions;
+        match self.placements.search_order() {
+            Option::Some(order) => solver.with_search_order(Arc::clone(order)),
+            Option::None => solver,
         }
-    );
-
-    // Now we start the solution loop. We will continue asking for the next piece to place until we
-    // find a solution that exceeds the ending_at path, or we run out of pieces to try in every
-    // possible position.
-    while next_piece.is_some() {
-        let p = next_piece.unwrap();
-
-        next_piece = match board.try_add_shape(p.shape, p.name) {
-            Result::Ok(_) => placements
-                .get_next_piece_to_try_after_success(p)
-                .map(|success| success.piece),
-            Result::Err(_) => get_next_piece_to_try_after_failure(&mut placements, &mut board, p),
+    }
+
+    /// The board with only the initial state on it.
+    pub fn initial_board(&self) -> &Board {
+        &self.initial_board
+    }
+
+    /// The pieces (and orientations) placed so far, in the order they were placed.
+    pub fn path(&self) -> Vec<RequestedPiece> {
+        self.placements.path()
+    }
+
+    /// The current state of the board. Before any solutions have been requested this is the
+    /// initial board state.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Searches until the next solution is found, but tries no more than `max_nodes` pieces.
+    /// This lets callers do something else (like saving a checkpoint) during a long search.
+    /// The iterator is the same as calling this until a solution is found.
+    pub fn search(&mut self, max_nodes: u64) -> SearchStep {
+        match self.find_next_solution(max_nodes) {
+            SearchStep::Found(solution)
+                if self
+                    .symmetry
+                    .as_ref()
+                    .is_some_and(|symmetry| !symmetry.is_canonical(&solution.board)) =>
+            {
+                SearchStep::Paused
+            }
+            step => step,
+        }
+    }
+
+    /// Splits the remaining search into independent subtrees by placing up to `depth` more
+    /// pieces. Each returned solver searches exactly one subtree and never removes the pieces
+    /// that were placed to create it.
+    ///
+    /// The subtrees are returned in the order this solver would have searched them, so chaining
+    /// the solutions of the returned solvers yields the same solutions in the same (lexical)
+    /// order. Pieces that were already placed before the split are never removed, even if
+    /// backtracking was allowed. The pieces tried to create the subtrees are counted in the
+    /// stats, so the subtrees add up to the same stats as searching without splitting.
+    pub fn split(&self, depth: usize) -> Vec<Solver> {
+        if depth == 0 || self.unreported_solution || self.next_piece.is_none() {
+            return vec![self.clone()];
+        }
+
+        // The cursor walks through every piece that fits in the next position. It never commits
+        // those pieces to its placements, so once it runs out of pieces to try in the position it
+        // stops instead of moving on to a different position.
+        let mut cursor = self.clone();
+        cursor
+            .placements
+            .prevent_backtracking_beyond_this_piece(Option::None);
+
+        let mut subtrees = Vec::new();
+        let mut nodes = 0u64;
+        let mut saved = 0u64;
+        while let Option::Some(p) = cursor.next_piece.take() {
+            nodes += 1;
+            let added = cursor.try_add_piece(&p);
+            if matches!(added, Result::Err(Rejected::DeadRegion)) && cursor.count_saved_nodes {
+                saved += cursor.count_pruned_subtree(&p);
+            }
+            if added.is_ok() {
+                let mut subtree = cursor.clone();
+                subtree.next_piece = subtree
+                    .placements
+                    .get_next_piece_to_try_after_success(p.clone())
+                    .map(|success| success.piece);
+                subtree
+                    .placements
+                    .prevent_backtracking_beyond_this_piece(Option::None);
+                subtree.unreported_solution =
+                    subtree.next_piece.is_none() && subtree.board.solved();
+
+                if subtree.next_piece.is_some() || subtree.unreported_solution {
+                    subtrees.append(&mut subtree.split(depth - 1));
+                }
+
+                cursor.board.remove_shape(p.name);
+            }
+            cursor.next_piece =
+                get_next_piece_to_try_after_failure(&mut cursor.placements, &mut cursor.board, p);
+        }
+
+        self.stats.add(nodes, saved);
+        subtrees
+    }
+
+    /// Adds the suggested piece to the board in the next open position. If it is rejected, the
+    /// board is left as it was.
+    fn try_add_piece(&mut self, piece: &PieceSuggestion) -> Result<(), Rejected> {
+        if self.board.try_add_piece(piece).is_err() {
+            return Result::Err(Rejected::DoesNotFit);
+        }
+
+        if let Option::Some(symmetry) = &self.symmetry {
+            let mask = self.board.mask_of(piece.name).unwrap();
+            if !symmetry.allows(piece.name, mask) {
+                self.board.remove_shape(piece.name);
+                return Result::Err(Rejected::Symmetric);
+            }
+        }
+
+        if self.prune_dead_regions && self.board.has_dead_region() {
+            self.board.remove_shape(piece.name);
+            return Result::Err(Rejected::DeadRegion);
+        }
+
+        Result::Ok(())
+    }
+
+    /// Counts the nodes a search without pruning would try below the piece, which was just
+    /// removed because it left a dead region behind. The piece is placed again in a copy of the
+    /// solver that never backtracks beyond it, and that copy searches the subtree below it.
+    fn count_pruned_subtree(&self, piece: &PieceSuggestion) -> u64 {
+        let mut subtree = self.clone();
+        subtree.prune_dead_regions = false;
+        subtree.count_saved_nodes = false;
+        subtree.stats = Arc::new(SearchStats::default());
+        subtree.profile = Option::None;
+        subtree
+            .board
+            .try_add_piece(piece)
+            .expect("The pruned piece fit before it was removed");
+        subtree.next_piece = subtree
+            .placements
+            .get_next_piece_to_try_after_success(piece.clone())
+            .map(|success| success.piece);
+        subtree
+            .placements
+            .prevent_backtracking_beyond_this_piece(Option::None);
+
+        // A board with a dead region has no solutions, so the search runs until it is finished.
+        while !matches!(subtree.find_next_solution(u64::MAX), SearchStep::Finished) {}
+        subtree.stats.nodes()
+    }
+
+    /// Returns the current solution and then removes the last placed piece so the search can
+    /// continue from where the solution was found.
+    fn take_solution(&mut self) -> Solution {
+        let solution = Solution {
+            placements: self.placements.clone(),
+            board: self.board.clone(),
         };
 
-        // If we have no pieces remaining to try then we can also check if the board is solved
-        // We avoid checking that if we still have pieces since it cannot be solved in that case,
-        // and it's just doing extra work.
-        if next_piece.is_none() && board.solved() {
-            if !placements.to_string().le(&ending_at_placement) {
-                // Additionally, we want to make sure that if we have a solution we only report it
-                // and continue finding more solutions, if our placement path is lexically less than
-                // the specified ending path. Note: this check being placed here means that we will
-                // always find one extra solution (and not report it) before exiting, but that is
-                // more convenient than making the caller define an exact path to stop at. (If they
-                // knew the exact path, they'd already know all the solutions and wouldn't need to
-                // call the function to begin with).
-                break;
+        // We found a solution, but there could be more. We'll remove the last piece we placed,
+        // pretend like it failed placement in the board, and try the next piece. If there are
+        // more solutions, this is enough to kick off the process again while ensuring we don't
+        // re-find any solutions we've already discovered.
+        self.unreported_solution = false;
+        self.next_piece = match self.placements.remove_last_piece() {
+            Option::Some(last) => {
+                self.board.remove_shape(last.name);
+                get_next_piece_to_try_after_failure(&mut self.placements, &mut self.board, last)
             }
+            _ => Option::None,
+        };
 
-            solutions += 1;
-            println!("{}", placements);
-            println!("{}", board);
-
-            // We found a solution, but there could be more. We'll remove the last piece we placed,
-            // pretend like it failed placement in the board, and try the next piece. If there are
-            // more solutions, this is enough to kick off the process again while ensuring we don't
-            // re-find any solutions we've already discovered.
-            next_piece = match placements.remove_last_piece() {
-                Option::Some(last) => {
-                    board.remove_shape(last.name);
-                    get_next_piece_to_try_after_failure(&mut placements, &mut board, last)
+        solution
+    }
+
+    /// The search described in `next`. Every solution is found, including ones that are not
+    /// canonical.
+    fn find_next_solution(&mut self, max_nodes: u64) -> SearchStep {
+        if self.unreported_solution {
+            return SearchStep::Found(self.take_solution());
+        }
+
+        // The counts are only added to the shared stats once we're done, so the hot loop doesn't
+        // have to touch them.
+        let mut nodes = 0u64;
+        let mut saved = 0u64;
+        let mut step = SearchStep::Paused;
+
+        // We will continue asking for the next piece to place until we find a solution, or we run
+        // out of pieces to try in every possible position.
+        while nodes < max_nodes {
+            let p = match self.next_piece.take() {
+                Option::Some(p) => p,
+                Option::None => {
+                    step = SearchStep::Finished;
+                    break;
+                }
+            };
+            nodes += 1;
+            let depth = self.placements.len();
+            let added = self.try_add_piece(&p);
+            let pruned = matches!(added, Result::Err(Rejected::DeadRegion));
+            if let Option::Some(profile) = &mut self.profile {
+                profile.add(depth.saturating_sub(profile.initial_pieces), added.is_ok());
+                if pruned {
+                    profile.pruned += 1;
+                }
+            }
+            if pruned && self.count_saved_nodes {
+                saved += self.count_pruned_subtree(&p);
+            }
+            self.next_piece = match added {
+                Result::Ok(_) => self
+                    .placements
+                    .get_next_piece_to_try_after_success(p)
+                    .map(|success| success.piece),
+                Result::Err(_) => {
+                    get_next_piece_to_try_after_failure(&mut self.placements, &mut self.board, p)
                 }
-                _ => Option::None,
+            };
+
+            // If we have no pieces remaining to try then we can also check if the board is solved
+            // We avoid checking that if we still have pieces since it cannot be solved in that
+            // case, and it's just doing extra work.
+            if self.next_piece.is_none() && self.board.solved() {
+                step = SearchStep::Found(self.take_solution());
+                break;
             }
         }
+
+        self.stats.add(nodes, saved);
+        step
     }
+}
 
-    println!("found {} solutions", solutions);
+impl Iterator for Solver {
+    type Item = Solution;
+
+    /// Finds the next solution. `None` is returned once all solutions beyond the initial state
+    /// have been found.
+    ///
+    /// The algorithm used here is a naive depth-first search for solutions. Starting at the
+    /// initial piece and orientation, all combinations of other pieces and orientations are
+    /// tried. The pieces and orientations are always tried in lexical order. If a piece cannot
+    /// be placed on the board, that tree will be abandoned.
+    ///
+    /// Pieces will always be placed in the top-most, left-most available space on the board
+    /// (except when added as part of the initial state).
+    ///
+    /// If the solver has a symmetry, solutions that are not canonical are skipped.
+    ///
+    /// A single iteration of placing a piece looks like the following:
+    /// 1. Attempt to place piece 'X' in orientation \[n\] into next board position
+    /// 2. If the piece fits:
+    ///    1. If the board is solved:
+    ///       1. Remove the last piece and pretend as if the last piece failed to be placed
+    ///       2. Return the solution
+    ///    2. If the board is not solved:
+    ///       1. Get the next piece to try to place and goto 1.
+    /// 3. If the piece does not fit:
+    ///    1. Ask for a new piece to try (this new piece and orientation will always be 'after'
+    ///       the failed piece).
+    ///    2. If all possible pieces have already been tried at this position, one or more pieces
+    ///       will be removed until we find another piece that can be tried.
+    ///    3. Goto 1 with the new piece to try.
+    fn next(&mut self) -> Option<Solution> {
+        loop {
+            match self.search(u64::MAX) {
+                SearchStep::Found(solution) => return Option::Some(solution),
+                SearchStep::Paused => {}
+                SearchStep::Finished => return Option::None,
+            }
+        }
+    }
 }
 
 /// Wraps `Placements`' `get_next_piece_to_try_after_failure` method to add logic to remove
 /// any necessary pieces from the `Board`.
 fn get_next_piece_to_try_after_failure(
     placements: &mut Placements,
-    board: &mut Variation,
+    board: &mut Board,
     failed_piece: PieceSuggestion,
 ) -> Option<PieceSuggestion> {
     match placements.get_next_piece_to_try_after_failure(failed_piece) {
@@ -123,115 +708,72 @@ fn get_next_piece_to_try_after_failure(
     }
 }
 
-/// Transforms a possible vector of `RequestedPiece`s into  a `String` that can be compared with a
+/// Transforms a vector of `RequestedPiece`s into a `String` that can be compared with a
 /// `Placements` path string.
-fn convert_requested_pieces_to_path_string(pieces: Option<Vec<RequestedPiece>>) -> String {
-    pieces
-        .or(Option::Some(Vec::new()))
-        .map(|pieces| {
-            if pieces.is_empty() {
-                // A placement string looks something like "A[0]; B[0]; etc..."
-                // If the vector is empty, that means all solutions greater than starting_at,
-                // are desired. The last piece is 'L' currently. That means that a string
-                // starting with 'Z' (or even M) will always come lexically after any valid
-                // placement path.
-                "Z-NO-LIMIT".to_string()
-            } else {
-                let mut string = Vec::new();
-                for piece in pieces {
-                    string.push(piece.name.to_string());
-                    string.push('['.to_string());
-                    if piece.orientation_index < 10 {
-                        string.push('0'.to_string());
-                    }
-                    string.push(piece.orientation_index.to_string());
-                    string.push(']'.to_string());
-                    string.push("; ".to_string());
-                }
-                string.join("")
-            }
-        })
-        .unwrap()
+fn convert_requested_pieces_to_path_string(pieces: Vec<RequestedPiece>) -> String {
+    let mut string = Vec::new();
+    for piece in pieces {
+        string.push(piece.name.to_string());
+        string.push('['.to_string());
+        if piece.orientation_index < 10 {
+            string.push('0'.to_string());
+        }
+        string.push(piece.orientation_index.to_string());
+        string.push(']'.to_string());
+        string.push("; ".to_string());
+    }
+    string.join("")
 }
 
 /// Initializes the board, the placement iterator and returns the first piece that needs
 /// to be placed.
-fn initialize(
+pub(crate) fn initialize(
     initial_state: Option<Vec<String>>,
     board_type: &BoardType,
-    allow_backtracking: Option<bool>,
-) -> (Variation, Placements, Option<PieceSuggestion>) {
+    allow_backtracking: bool,
+) -> Result<(Board, Placements, Option<PieceSuggestion>), Error> {
+    board_type.check_cell_count()?;
+
     // Default to an empty board
-    let board_state = initial_state.unwrap_or(Vec::new());
+    let board_state = initial_state.unwrap_or_default();
 
     // While placements track which pieces and orientations have been tried, the board tracks
     // where the pieces are placed, whether a piece will fit, and whether the board is in the
     // solved state.
-    let mut board = create_board(board_type);
+    let mut board = Board::new(board_type);
 
     // Placements keeps track of the available and used pieces at any given time. Pieces are always
     // ordered lexically (by name and then orientation) to ensure that once we've attempted to place
     // a piece in a specific context (previous pieces/orientation and positions), we will never try
     // that same permutation again. That means that as we ask for pieces, we'll eventually run out
     // of permutations to try and the algorithm will halt.
-    let mut placements = Placements::new(*board_type == BoardType::Pyramid);
+    let mut placements = Placements::new(board_type);
 
     let mut requested_pieces = Vec::new();
 
     // We're going to try to populate the board with shapes that match the initial board state.
-    let piece_names = get_sorted_piece_names();
+    let piece_names = board_type.pieces().names();
     for piece_name in piece_names {
-        match Shape::parse(&board_state, *piece_name) {
-            Option::Some(shape) => {
-                let piece = get_piece(piece_name);
-                // This builds a string when we might not need it, but it just happens on startup
-                // for at most 12 pieces. So it really shouldn't matter.
-                let expectation = format!(
-                    "Unrecognized piece orientation for [{}]\n{}",
-                    piece_name, shape
-                );
-
-                let orientation_index = piece
-                    .orientations
-                    .iter()
-                    .position(|s| shape.eq(s))
-                    .expect(&expectation);
-
-                requested_pieces.push(RequestedPiece {
-                    name: *piece_name,
-                    orientation_index,
-                });
-
-                let shape_position = parse_shape_position(&board_state, piece_name);
-
-                if let Result::Err(_) = board.try_add_shape_at(&shape, *piece_name, &shape_position)
-                {
-                    panic!("Unable to add initial piece {} to board at ({}, {}, {}). It does not fit. Initialization failed.",
-                           piece.letter, shape_position.0, shape_position.1, shape_position.2);
-                }
-            }
-            Option::None => {
-                // This is fine. If the piece wasn't requested, we won't try to add it.
-            }
+        // If the piece wasn't requested, we won't try to add it.
+        if let Option::Some(requested) = add_initial_piece(&mut board, &board_state, *piece_name)? {
+            requested_pieces.push(requested);
         }
     }
 
     // Now that we've successfully added the shapes in the initial state to the board, we need to
     // initialize our placements iterator with those pieces.
     let suggestion = if requested_pieces.is_empty() {
-        // The initial state was empty. Therefore we need to initialize with A[0].
+        // The initial state was empty. Therefore we need to initialize with the first piece (A[0]).
         // There are no shapes on the board currently, so the piece that is returned
         // here can kick off our solution loop.
-        placements
-            .initialize(vec![RequestedPiece {
-                name: 'A',
-                orientation_index: 0,
-            }])
-            .pop()
+        placements.first_suggestion()
     } else {
         // The initial state had some shapes, so push them all into the iterator.
         // We need the last initialized piece to get the _next_ piece from the iterator.
-        let last_requested = placements.initialize(requested_pieces).pop().unwrap();
+        let last_requested = placements
+            .initialize(requested_pieces)?
+            .pop()
+            .expect("Every initial piece has a suggestion");
 
         // We know this piece fits (we added it to the board already). We need to get the _next_
         // piece and return it as the piece to kick off our solution loop. This gets us into an
@@ -241,49 +783,192 @@ fn initialize(
             .map(|success| success.piece)
     };
 
-    if !allow_backtracking.unwrap_or(false) {
+    if !allow_backtracking {
         placements.prevent_backtracking_beyond_this_piece(Option::None);
     }
 
-    println!("Initial board state");
-    println!("{}", board);
+    Result::Ok((board, placements, suggestion))
+}
 
-    return (board, placements, suggestion);
+/// A piece of a board state and its orientation, or why it can't be used (see `check_pieces`).
+pub type PieceCheck = (char, Result<RequestedPiece, Error>);
+
+/// Checks every piece of a board state the same way `Solver::new` does, but doesn't stop at the
+/// first piece that can't be used. Returns the board with every piece that could be added to it,
+/// and the orientation of every piece in the state (or why it can't be used), in name order.
+/// Pieces that are not in the state are left out.
+pub fn check_pieces(board_type: &BoardType, board_state: &[String]) -> (Board, Vec<PieceCheck>) {
+    let mut board = Board::new(board_type);
+    let mut pieces = Vec::new();
+    for piece_name in board_type.pieces().names() {
+        match add_initial_piece(&mut board, board_state, *piece_name) {
+            Result::Ok(Option::None) => {}
+            Result::Ok(Option::Some(requested)) => {
+                pieces.push((*piece_name, Result::Ok(requested)))
+            }
+            Result::Err(e) => pieces.push((*piece_name, Result::Err(e))),
+        }
+    }
+    (board, pieces)
 }
 
-fn parse_shape_position(board_state: &Vec<String>, letter: &char) -> Position {
-    let layer_count = board_state.len();
-    for layer_index in 0..layer_count {
-        let chars = board_state.get(layer_index).unwrap().chars();
-        let mut row = 0;
-        let mut col = 0;
-
-        for ch in chars {
-            match ch {
-                '\n' => {
-                    row += 1;
-                    col = 0;
-                }
-                c if c == *letter => {
-                    return Position(layer_index, row, col);
+/// Adds the piece with the name to the board, in the shape and position it has in the board
+/// state. Returns the piece's orientation, or `None` if the piece is not in the board state.
+fn add_initial_piece(
+    board: &mut Board,
+    board_state: &[String],
+    piece_name: char,
+) -> Result<Option<RequestedPiece>, Error> {
+    let cells = parse_cell_positions(board_state, &piece_name);
+    let shape_position = match cells.first() {
+        Option::Some(position) => *position,
+        Option::None => return Result::Ok(Option::None),
+    };
+
+    // A piece that runs off the board is reported as such, rather than as a shape that isn't one
+    // of the piece's orientations, so that is checked first.
+    let masks = board.masks();
+    for Position(layer, row, col) in &cells {
+        if *layer >= masks.layer_count() {
+            return Result::Err(Error::MalformedBoard(format!(
+                "Piece {} is in layer {}, but the {:?} board only has {} layer(s)",
+                piece_name,
+                layer + 1,
+                board.board_type(),
+                masks.layer_count()
+            )));
+        }
+        let (rows, cols) = masks.dimensions(*layer);
+        if *row >= rows || *col >= cols {
+            return Result::Err(Error::MalformedBoard(format!(
+                "Piece {} is in row {}, column {} of layer {}, but the layer is only {} x {}",
+                piece_name,
+                row + 1,
+                col + 1,
+                layer + 1,
+                rows,
+                cols
+            )));
+        }
+    }
+
+    let piece = &board.pieces()[&piece_name];
+    let cell_count = piece.orientations[0].cell_count();
+    // The cells of a piece always touch each other, so a letter whose cells are in separate
+    // groups (or that has more cells than the piece) was used for more than one piece.
+    if cells.len() > cell_count || !is_connected(&cells) {
+        return Result::Err(Error::DuplicatePiece(piece_name));
+    }
+    let not_found = || Error::OrientationNotFound {
+        piece: piece_name,
+        shape: draw_cells(&cells, piece_name),
+    };
+    if cells.len() != cell_count {
+        return Result::Err(not_found());
+    }
+    // Orientations start in their lowest layer, so the shape is parsed from the layer the
+    // piece starts in. (A flat piece on the 3rd layer of the pyramid is still flat.)
+    let shape = Shape::parse(&board_state[shape_position.0..], piece_name).ok_or_else(not_found)?;
+    let orientation_index = piece
+        .orientations
+        .iter()
+        .position(|s| shape.eq(s))
+        .ok_or_else(not_found)?;
+
+    board
+        .try_add_shape_at(&shape, piece_name, &shape_position)
+        .map_err(|_| Error::PieceDoesNotFit {
+            piece: piece_name,
+            position: shape_position,
+        })?;
+
+    Result::Ok(Option::Some(RequestedPiece {
+        name: piece_name,
+        orientation_index,
+    }))
+}
+
+/// Determines if every cell can be reached from the first one by moving to a cell it touches.
+/// Cells touch the cells next to them in the same layer, and the cells they rest on or that rest
+/// on them in the layers below and above, the same way `ShapeMasks::neighbors` are found.
+fn is_connected(cells: &[Position]) -> bool {
+    let touches = |a: &Position, b: &Position| {
+        let (upper, lower) = if a.0 > b.0 { (a, b) } else { (b, a) };
+        match upper.0 - lower.0 {
+            0 => upper.1.abs_diff(lower.1) + upper.2.abs_diff(lower.2) == 1,
+            // A cell rests on the 4 cells below it and to the right and bottom of it.
+            1 => lower.1.wrapping_sub(upper.1) < 2 && lower.2.wrapping_sub(upper.2) < 2,
+            _ => false,
+        }
+    };
+
+    let mut reached = vec![false; cells.len()];
+    let mut to_visit = vec![0];
+    while let Option::Some(index) = to_visit.pop() {
+        if reached[index] {
+            continue;
+        }
+        reached[index] = true;
+        to_visit.extend((0..cells.len()).filter(|i| touches(&cells[index], &cells[*i])));
+    }
+    reached.iter().all(|reached| *reached)
+}
+
+/// Lists the positions of the cells with the specified letter in the board state, in the order
+/// `Board::positions` lists them.
+fn parse_cell_positions(board_state: &[String], letter: &char) -> Vec<Position> {
+    let mut positions = Vec::new();
+    for (layer_index, layer) in board_state.iter().enumerate() {
+        for (row, line) in layer.split('\n').enumerate() {
+            for (col, ch) in line.chars().enumerate() {
+                if ch == *letter {
+                    positions.push(Position(layer_index, row, col));
                 }
-                _ => col += 1,
             }
         }
     }
-
-    panic!(
-        "Shape {} requested, but not found in initial board state",
-        letter
-    );
+    positions
 }
 
-fn get_sorted_piece_names() -> Vec<&'static char> {
-    let mut keys: Vec<&char> = (*PIECES).keys().collect();
-    keys.sort();
-    return keys;
+/// Draws the smallest part of the board state that holds all the cells, the same way board
+/// states are written (layers are separated by an empty line).
+fn draw_cells(cells: &[Position], letter: char) -> String {
+    let rows = cells.iter().map(|Position(_, row, _)| *row);
+    let cols = cells.iter().map(|Position(_, _, col)| *col);
+    let (first_row, last_row) = (rows.clone().min().unwrap_or(0), rows.max().unwrap_or(0));
+    let (first_col, last_col) = (cols.clone().min().unwrap_or(0), cols.max().unwrap_or(0));
+
+    let mut layers: Vec<usize> = cells.iter().map(|Position(layer, _, _)| *layer).collect();
+    layers.dedup();
+    layers
+        .iter()
+        .map(|layer| {
+            (first_row..=last_row)
+                .map(|row| {
+                    (first_col..=last_col)
+                        .map(|col| {
+                            if cells.contains(&Position(*layer, row, col)) {
+                                letter
+                            } else {
+                                '.'
+                            }
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
 }
 
-fn get_piece(name: &char) -> &Piece {
-    (*PIECES).get(name).unwrap()
+/// The shape of the piece in the requested orientation, or `None` if there is no such piece or
+/// orientation.
+fn get_piece_orientation_checked<'a>(
+    pieces: &'a PieceSet,
+    piece: &RequestedPiece,
+) -> Option<&'a Shape> {
+    pieces
+        .get(&piece.name)
+        .and_then(|p| p.orientations.get(piece.orientation_index))
 }
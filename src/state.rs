@@ -0,0 +1,249 @@
+use crate::board::{Board, BoardType, BLOCKED_SLOT};
+use crate::error::Error;
+use crate::layer::Position;
+use crate::pieces::EMPTY_CELLS;
+
+/// Reads a board state (such as an initial state) into layers, in the format `Solver::new`
+/// expects. New lines separate rows and empty lines separate layers, from the bottom layer to the
+/// top one. The names of the board's pieces (see `BoardType::pieces`), such as `A` - `L` or any
+/// other letter or symbol of a piece file, are parts of pieces. `.`, `·` and spaces are empty
+/// cells, and `#` is a blocked cell (see `BoardMask`), the same way `Board::state` writes them.
+/// Windows (`\r\n`) line endings are fine, and so are empty lines at the end.
+///
+/// The state is checked against the board type: there may not be more layers, rows or columns
+/// than the board has, pieces may not be on blocked cells and `#` may only be on them. Any other
+/// character is rejected too, since it is almost always a typo. Errors name the line and column
+/// (both counted from 1).
+///
+/// Whitespace at the end of a row, and rows with nothing but whitespace, may run past the edge of
+/// the board.
+pub fn parse_state(input: &str, board_type: &BoardType) -> Result<Vec<String>, Error> {
+    let board = Board::new(board_type);
+    let masks = board.masks();
+    let pieces = board.pieces();
+    let invalid = |line: usize, column: usize, message: String| Error::InvalidState {
+        line,
+        column,
+        message,
+    };
+
+    let layers = split_layers(input);
+    let mut line = 1;
+    for (layer, rows) in layers.iter().enumerate() {
+        let rows: Vec<&str> = rows.split('\n').collect();
+        if layer >= masks.layer_count() && rows.iter().any(|row| !row.trim().is_empty()) {
+            return Result::Err(invalid(
+                line,
+                1,
+                format!(
+                    "The {:?} board only has {} layer(s), but this is layer {}",
+                    board_type,
+                    masks.layer_count(),
+                    layer + 1
+                ),
+            ));
+        }
+
+        let (row_count, col_count) = if layer < masks.layer_count() {
+            masks.dimensions(layer)
+        } else {
+            (0, 0)
+        };
+        for (row, text) in rows.iter().enumerate() {
+            for (col, ch) in text.chars().enumerate() {
+                if ch.is_whitespace() {
+                    continue;
+                }
+                if !pieces.contains(&ch) && !EMPTY_CELLS.contains(&ch) && ch != BLOCKED_SLOT {
+                    let message = if ch.is_alphabetic() {
+                        format!(
+                            "Unknown piece {} (expected {} or an empty cell)",
+                            ch,
+                            pieces.describe_names()
+                        )
+                    } else {
+                        format!(
+                            "Unexpected {} (expected {}, . or a space for an empty cell, or # for \
+                             a blocked cell)",
+                            ch,
+                            pieces.describe_names()
+                        )
+                    };
+                    return Result::Err(invalid(line + row, col + 1, message));
+                }
+                if row >= row_count {
+                    return Result::Err(invalid(
+                        line + row,
+                        col + 1,
+                        format!("Layer {} only has {} rows", layer + 1, row_count),
+                    ));
+                }
+                if col >= col_count {
+                    return Result::Err(invalid(
+                        line + row,
+                        col + 1,
+                        format!("Layer {} only has {} columns", layer + 1, col_count),
+                    ));
+                }
+                let open = masks.is_open(&Position(layer, row, col));
+                if pieces.contains(&ch) && !open {
+                    return Result::Err(invalid(
+                        line + row,
+                        col + 1,
+                        format!("Piece {} is on a blocked cell", ch),
+                    ));
+                }
+                if ch == BLOCKED_SLOT && open {
+                    return Result::Err(invalid(
+                        line + row,
+                        col + 1,
+                        "# is a blocked cell, but this cell is open".to_string(),
+                    ));
+                }
+            }
+        }
+
+        // The empty line between layers is a line too.
+        line += rows.len() + 1;
+    }
+
+    Result::Ok(layers)
+}
+
+/// Reads a board state without checking it against a board (see `parse_state`). Any character
+/// that isn't a piece letter is an empty cell, including letters that aren't piece names. Pieces
+/// that don't fit on the board are still reported by `Solver::new`.
+pub fn parse_state_leniently(input: &str) -> Vec<String> {
+    split_layers(input)
+}
+
+/// Splits the input into layers on empty lines, after removing `\r` line endings and empty lines
+/// at the end.
+fn split_layers(input: &str) -> Vec<String> {
+    let input = input.replace("\r\n", "\n");
+    input
+        .trim_end_matches(['\n', '\r'])
+        .split("\n\n")
+        .map(|layer| layer.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_file::BoardMask;
+    use std::sync::Arc;
+
+    fn error_at(input: &str, board_type: &BoardType) -> (usize, usize, String) {
+        match parse_state(input, board_type) {
+            Result::Err(Error::InvalidState {
+                line,
+                column,
+                message,
+            }) => (line, column, message),
+            other => panic!("Expected an invalid state, got {:?}", other),
+        }
+    }
+
+    fn tray() -> BoardType {
+        BoardType::Custom(Arc::new(
+            BoardMask::parse("#...\n....", "tray.txt").unwrap(),
+        ))
+    }
+
+    #[test]
+    fn states_are_split_into_layers() {
+        let state = parse_state("AAA\nA..\n\n\n\n.B", &BoardType::Pyramid).unwrap();
+        assert_eq!(state, vec!["AAA\nA..", "", ".B"]);
+        let state = parse_state("#A\r\n.A\r\n\r\n", &tray()).unwrap();
+        assert_eq!(state, vec!["#A\n.A"]);
+    }
+
+    #[test]
+    fn letters_that_are_not_pieces_are_rejected() {
+        assert_eq!(
+            error_at("..A\n.M.", &BoardType::Rectangle),
+            (
+                2,
+                2,
+                "Unknown piece M (expected A - L or an empty cell)".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn symbols_that_are_not_pieces_are_rejected() {
+        assert_eq!(
+            error_at("AA*", &BoardType::Rectangle),
+            (
+                1,
+                3,
+                "Unexpected * (expected A - L, . or a space for an empty cell, or # for a \
+                 blocked cell)"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            error_at("..#", &tray()),
+            (
+                1,
+                3,
+                "# is a blocked cell, but this cell is open".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn states_larger_than_the_board_are_rejected() {
+        assert_eq!(
+            error_at("A\n\n\nB", &BoardType::Rectangle),
+            (
+                3,
+                1,
+                "The Rectangle board only has 1 layer(s), but this is layer 2".to_string()
+            )
+        );
+        assert_eq!(
+            error_at("A\n\n\n\n\n\n\n\n\n\n\n..B", &BoardType::Pyramid),
+            (
+                11,
+                1,
+                "The Pyramid board only has 5 layer(s), but this is layer 6".to_string()
+            )
+        );
+        assert_eq!(
+            error_at(".\n.\n.\n.\n.\nA", &BoardType::Rectangle),
+            (6, 1, "Layer 1 only has 5 rows".to_string())
+        );
+        assert_eq!(
+            error_at("...........A", &BoardType::Rectangle),
+            (1, 12, "Layer 1 only has 11 columns".to_string())
+        );
+        // Trailing whitespace may run past the edge.
+        assert!(parse_state("........... \n\n  \n", &BoardType::Rectangle).is_ok());
+    }
+
+    #[test]
+    fn windows_line_endings_do_not_shift_columns() {
+        assert_eq!(
+            error_at("AA\r\n.....M", &BoardType::Rectangle),
+            (
+                2,
+                6,
+                "Unknown piece M (expected A - L or an empty cell)".to_string()
+            )
+        );
+        assert_eq!(
+            error_at("AAA\r\nA\r\n\r\n.....", &BoardType::Pyramid),
+            (4, 5, "Layer 2 only has 4 columns".to_string())
+        );
+    }
+
+    #[test]
+    fn pieces_on_blocked_cells_are_rejected() {
+        assert_eq!(
+            error_at("A", &tray()),
+            (1, 1, "Piece A is on a blocked cell".to_string())
+        );
+    }
+}
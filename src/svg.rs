@@ -0,0 +1,185 @@
+use crate::board::Board;
+use crate::color::piece_color;
+use crate::layer::Position;
+
+/// The distance between the centers of neighboring cells.
+const CELL: usize = 40;
+const RADIUS: usize = 17;
+/// The width of the bars that connect the cells of a piece.
+const BAR: usize = 12;
+const MARGIN: usize = 20;
+/// The room taken by a caption (or a layer label) above a board (or a layer).
+const CAPTION: usize = 24;
+
+impl Board {
+    /// The board as an SVG image. Every piece is drawn as balls in the piece's color (see
+    /// `color::piece_color`), connected by bars. A flat board is drawn as a single grid. The
+    /// layers of a board with more than one layer (such as the pyramid) are drawn next to each
+    /// other, from the bottom layer to the top one. Blocked cells are left out.
+    pub fn to_svg(&self) -> String {
+        let (width, height) = self.svg_size();
+        svg_document(width, height, &self.svg_elements(0))
+    }
+
+    /// The width and height of the image drawn by `svg_elements`.
+    fn svg_size(&self) -> (usize, usize) {
+        let masks = self.masks();
+        let mut width = MARGIN;
+        let mut height = 0;
+        for layer in 0..masks.layer_count() {
+            let (rows, cols) = masks.dimensions(layer);
+            width += cols * CELL + MARGIN;
+            height = height.max(rows * CELL);
+        }
+        (width, height + self.layer_label_height() + 2 * MARGIN)
+    }
+
+    /// The layers are only labeled if the board has more than one layer (such as the pyramid or
+    /// a stacked board read from a file).
+    fn layer_label_height(&self) -> usize {
+        if self.masks().layer_count() > 1 {
+            CAPTION
+        } else {
+            0
+        }
+    }
+
+    /// The elements that draw the board, moved down by `top`.
+    fn svg_elements(&self, top: usize) -> String {
+        let masks = self.masks();
+        let layer_count = masks.layer_count();
+        let mut bars = String::new();
+        let mut balls = String::new();
+
+        let mut left = MARGIN;
+        let top = top + MARGIN + self.layer_label_height();
+        for layer in 0..layer_count {
+            let (rows, cols) = masks.dimensions(layer);
+            if layer_count > 1 {
+                let label = match layer {
+                    0 => format!("Layer {} (bottom)", layer + 1),
+                    l if l == layer_count - 1 => format!("Layer {} (top)", layer + 1),
+                    _ => format!("Layer {}", layer + 1),
+                };
+                balls.push_str(&format!(
+                    r#"<text x="{}" y="{}" font-family="sans-serif" font-size="14">{}</text>"#,
+                    left,
+                    top - 8,
+                    label
+                ));
+                balls.push('\n');
+            }
+
+            let center = |row: usize, col: usize| {
+                (left + col * CELL + CELL / 2, top + row * CELL + CELL / 2)
+            };
+            for row in 0..rows {
+                for col in 0..cols {
+                    if !masks.is_open(&Position(layer, row, col)) {
+                        continue;
+                    }
+                    let (x, y) = center(row, col);
+                    let letter = match self.letter_at(&Position(layer, row, col)) {
+                        Option::Some(letter) => letter,
+                        Option::None => {
+                            balls.push_str(&format!(
+                                r##"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="#cccccc"/>"##,
+                                x, y, RADIUS
+                            ));
+                            balls.push('\n');
+                            continue;
+                        }
+                    };
+
+                    let color = piece_color(letter, self.pieces());
+                    let (fill, text) = (color.rgb, if color.dark { "#ffffff" } else { "#000000" });
+                    // Bars to the neighbors in the same layer that belong to the same piece.
+                    for (r, c) in [(row, col + 1), (row + 1, col)] {
+                        if r < rows
+                            && c < cols
+                            && self.letter_at(&Position(layer, r, c)) == Option::Some(letter)
+                        {
+                            let (x2, y2) = center(r, c);
+                            bars.push_str(&format!(
+                                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+                                x, y, x2, y2, fill, BAR
+                            ));
+                            bars.push('\n');
+                        }
+                    }
+                    balls.push_str(&format!(
+                        r##"<circle cx="{}" cy="{}" r="{}" fill="{}" stroke="#333333"/>"##,
+                        x, y, RADIUS, fill
+                    ));
+                    balls.push_str(&format!(
+                        r#"<text x="{}" y="{}" font-family="sans-serif" font-size="14" text-anchor="middle" dominant-baseline="central" fill="{}">{}</text>"#,
+                        x,
+                        y,
+                        text,
+                        escape(&letter.to_string())
+                    ));
+                    balls.push('\n');
+                }
+            }
+            left += cols * CELL + MARGIN;
+        }
+
+        bars + &balls
+    }
+}
+
+/// Draws several boards in one SVG image, one below the other, each with a caption (such as its
+/// placements path) above it.
+pub fn stacked_svg(boards: &[(String, Board)]) -> String {
+    let mut elements = String::new();
+    let mut width = 0;
+    let mut top = 0;
+    for (caption, board) in boards {
+        let (board_width, board_height) = board.svg_size();
+        elements.push_str(&format!(
+            r#"<text x="{}" y="{}" font-family="sans-serif" font-size="14">{}</text>"#,
+            MARGIN,
+            top + MARGIN + 14,
+            escape(caption)
+        ));
+        elements.push('\n');
+        elements.push_str(&board.svg_elements(top + CAPTION));
+        width = width.max(board_width);
+        top += CAPTION + board_height;
+    }
+    svg_document(width.max(2 * MARGIN), top.max(2 * MARGIN), &elements)
+}
+
+fn svg_document(width: usize, height: usize, elements: &str) -> String {
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            "\n",
+            r#"<rect width="{0}" height="{1}" fill="white"/>"#,
+            "\n{2}</svg>\n"
+        ),
+        width, height, elements
+    )
+}
+
+/// Escapes the characters that have a special meaning in SVG text and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_characters_are_escaped() {
+        assert_eq!(escape("A[00]; B[03]; "), "A[00]; B[03]; ");
+        assert_eq!(escape("<A & B>"), "&lt;A &amp; B&gt;");
+        assert_eq!(escape(r#""A" 'B'"#), "&quot;A&quot; &#39;B&#39;");
+        assert_eq!(escape("&lt;"), "&amp;lt;");
+    }
+}
@@ -0,0 +1,262 @@
+use crate::bitboard::ShapeMasks;
+use crate::board::Board;
+use crate::layer::Position;
+use crate::placements::RequestedPiece;
+use crate::solver::Solution;
+use crate::Placements;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// How solutions that are mirror images or rotations of each other are reported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymmetryMode {
+    /// Only report one (canonical) solution per symmetry class.
+    Reduce,
+    /// Search for the canonical solutions and derive the symmetric ones by transforming them.
+    Expand,
+}
+
+impl FromStr for SymmetryMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("reduce") {
+            Result::Ok(SymmetryMode::Reduce)
+        } else if s.eq_ignore_ascii_case("expand") {
+            Result::Ok(SymmetryMode::Expand)
+        } else {
+            Result::Err(format!(
+                "Unknown symmetry mode {} (expected reduce or expand)",
+                s
+            ))
+        }
+    }
+}
+
+/// A mirror image or rotation that maps the board onto itself.
+struct Transformation {
+    /// The bit every cell is moved to, indexed by the cell's bit. (See `ShapeMasks`)
+    bits: Vec<usize>,
+}
+
+impl Transformation {
+    fn apply(&self, mask: u64) -> u64 {
+        let mut remaining = mask;
+        let mut transformed = 0u64;
+        while remaining != 0 {
+            let index = remaining.trailing_zeros() as usize;
+            remaining &= remaining - 1;
+            transformed |= 1u64 << self.bits[index];
+        }
+        transformed
+    }
+}
+
+/// The symmetries of a board and its initial state.
+///
+/// The rectangle can be mirrored horizontally and vertically (and both, which is the same as
+/// rotating it 180 degrees). Every layer of the pyramid is a square, so it can also be rotated by
+/// 90 degrees. Mirroring or rotating a solution always results in another solution, since every
+/// mirrored and rotated orientation of every piece is available.
+///
+/// Only symmetries that leave the pieces of the initial state where they are apply. An empty
+/// board has every symmetry, but most initial states have none at all.
+///
+/// Every solution belongs to a class of solutions that are symmetric to each other. One of them
+/// is the canonical solution. Only one piece (the first piece not in the initial state) is needed
+/// to find it: the piece must fill the cells with the lowest mask out of all of the transformed
+/// versions of those cells. A solver that only places that piece in those cells (see `allows`)
+/// skips most of the symmetric solutions without having to search for them. If the piece's cells
+/// are symmetric themselves, more than one solution of the class is left. Out of those, the one
+/// with the lowest piece masks is canonical.
+pub struct Symmetry {
+    initial_board: Board,
+    transformations: Vec<Transformation>,
+    /// The piece whose placements are restricted, or `None` if every piece is on the board.
+    piece: Option<char>,
+    piece_names: Vec<char>,
+}
+
+impl Symmetry {
+    /// Finds the symmetries of a board that has only the initial state on it.
+    pub fn new(initial_board: &Board) -> Symmetry {
+        let piece_names: Vec<char> = initial_board.pieces().names().to_vec();
+        let placed: Vec<u64> = piece_names
+            .iter()
+            .filter_map(|name| initial_board.mask_of(*name))
+            .collect();
+
+        let transformations = all_transformations(initial_board.masks())
+            .into_iter()
+            .filter(|t| placed.iter().all(|mask| t.apply(*mask) == *mask))
+            .collect();
+
+        Symmetry {
+            initial_board: initial_board.clone(),
+            transformations,
+            piece: piece_names
+                .iter()
+                .copied()
+                .find(|name| initial_board.mask_of(*name).is_none()),
+            piece_names,
+        }
+    }
+
+    /// The number of transformations (including leaving the board as it is) that map the board
+    /// and the initial state onto themselves.
+    pub fn transformation_count(&self) -> usize {
+        self.transformations.len()
+    }
+
+    /// Determines if a piece may be placed in the specified cells. Only the restricted piece is
+    /// ever disallowed, and only if a transformed version of the cells has a lower mask.
+    pub fn allows(&self, name: char, mask: u64) -> bool {
+        self.piece != Option::Some(name)
+            || self.transformations.iter().all(|t| t.apply(mask) >= mask)
+    }
+
+    /// Determines if a solution is the canonical solution of its symmetry class. The restricted
+    /// piece is expected to be placed in cells that are allowed.
+    pub fn is_canonical(&self, board: &Board) -> bool {
+        let masks = self.masks(board);
+        self.transformations
+            .iter()
+            .filter(|t| {
+                self.piece
+                    .and_then(|name| board.mask_of(name))
+                    .is_none_or(|mask| t.apply(mask) == mask)
+            })
+            .all(|t| self.transform(&masks, t) >= masks)
+    }
+
+    /// The number of distinct solutions in the solution's symmetry class (including itself).
+    pub fn class_size(&self, board: &Board) -> usize {
+        let masks = self.masks(board);
+        self.transformations
+            .iter()
+            .map(|t| self.transform(&masks, t))
+            .collect::<HashSet<Vec<u64>>>()
+            .len()
+    }
+
+    /// Every distinct solution in the solution's symmetry class. The solution itself is first.
+    pub fn expand(&self, solution: Solution) -> Vec<Solution> {
+        let masks = self.masks(&solution.board);
+        let initial_path: Vec<RequestedPiece> = solution
+            .placements
+            .path()
+            .into_iter()
+            .filter(|piece| self.initial_board.mask_of(piece.name).is_some())
+            .collect();
+
+        let mut seen = HashSet::new();
+        seen.insert(masks.clone());
+        let mut solutions = vec![solution];
+        // The first transformation leaves the board as it is.
+        for t in self.transformations.iter().skip(1) {
+            let transformed = self.transform(&masks, t);
+            if seen.insert(transformed.clone()) {
+                solutions.push(self.to_solution(initial_path.clone(), &transformed));
+            }
+        }
+        solutions
+    }
+
+    /// The cells filled by every piece, in piece name order.
+    fn masks(&self, board: &Board) -> Vec<u64> {
+        self.piece_names
+            .iter()
+            .map(|name| board.mask_of(*name).unwrap_or(0))
+            .collect()
+    }
+
+    fn transform(&self, masks: &[u64], t: &Transformation) -> Vec<u64> {
+        masks.iter().map(|mask| t.apply(*mask)).collect()
+    }
+
+    /// Places the pieces in the cells of the masks the same way the depth-first solver would
+    /// have: every piece is added in the top-most, left-most empty cell.
+    fn to_solution(&self, mut path: Vec<RequestedPiece>, masks: &[u64]) -> Solution {
+        let mut board = self.initial_board.clone();
+        let pieces = Arc::clone(board.pieces());
+
+        while !board.solved() {
+            let index = board.masks().index_of(board.next_pos());
+            let bit = 1u64 << index;
+            let (name, mask) = self
+                .piece_names
+                .iter()
+                .zip(masks)
+                .find(|(_, mask)| *mask & bit != 0)
+                .expect("Every cell of a transformed solution is filled");
+
+            let orientations = &pieces[name].orientations;
+            let orientation_index = (0..orientations.len())
+                .find(|o| board.masks().mask_at(*name, *o, index) == Option::Some(*mask))
+                .expect("Every transformed piece has a matching orientation");
+
+            board
+                .try_add_shape(&orientations[orientation_index], *name)
+                .expect("Pieces in a transformed solution never overlap");
+            path.push(RequestedPiece {
+                name: *name,
+                orientation_index,
+            });
+        }
+
+        Solution {
+            placements: Placements::with_path(&self.initial_board.board_type(), path)
+                .expect("A transformed solution has every piece once"),
+            board,
+        }
+    }
+}
+
+/// Every combination of mirroring the rows, mirroring the columns and (if every layer is a
+/// square) swapping the rows and columns of the layers. The first transformation leaves the
+/// board as it is.
+///
+/// Transformations that would move an open cell onto a blocked one (see `BoardMask`) are left
+/// out. Layers are only centered on the layer below them if every layer has one row and one
+/// column less than the layer below, like the pyramid. Other stacks of layers are never mirrored,
+/// since a mirrored piece would no longer rest on the same cells.
+fn all_transformations(masks: &ShapeMasks) -> Vec<Transformation> {
+    let square = (0..masks.layer_count()).all(|layer| {
+        let (rows, cols) = masks.dimensions(layer);
+        rows == cols
+    });
+    let centered = (1..masks.layer_count()).all(|layer| {
+        let (rows, cols) = masks.dimensions(layer);
+        let (rows_below, cols_below) = masks.dimensions(layer - 1);
+        rows + 1 == rows_below && cols + 1 == cols_below
+    });
+
+    let mut transformations = Vec::new();
+    for transpose in [false, true] {
+        if transpose && !square {
+            continue;
+        }
+        for mirror_rows in [false, true] {
+            for mirror_cols in [false, true] {
+                if (transpose || mirror_rows || mirror_cols) && !centered {
+                    continue;
+                }
+                let bits: Option<Vec<usize>> = (0..masks.cell_count())
+                    .map(|index| {
+                        let Position(layer, row, col) = *masks.position_of(index);
+                        let (rows, cols) = masks.dimensions(layer);
+                        let (row, col) = if transpose { (col, row) } else { (row, col) };
+                        let row = if mirror_rows { rows - 1 - row } else { row };
+                        let col = if mirror_cols { cols - 1 - col } else { col };
+                        masks.try_index_of(&Position(layer, row, col))
+                    })
+                    .collect();
+                if let Option::Some(bits) = bits {
+                    transformations.push(Transformation { bits });
+                }
+            }
+        }
+    }
+    transformations
+}
@@ -0,0 +1,31 @@
+use kanoodle_solver::bitboard::ShapeMasks;
+use kanoodle_solver::{Error, GridLayers, Layers, PieceSet};
+
+#[test]
+fn boards_that_do_not_fit_in_a_mask_are_rejected() {
+    let too_large = GridLayers::new(&[(5, 13)], '·');
+    assert_eq!(
+        ShapeMasks::new(&too_large, PieceSet::kanoodle()).err(),
+        Some(Error::MalformedBoard(
+            "The board has 65 open cells, but boards may have 64 at the most".to_string()
+        ))
+    );
+
+    // Cells that don't hold the default value are blocked.
+    let mut blocked = GridLayers::new(&[(2, 2)], '·');
+    for row in 0..2 {
+        for col in 0..2 {
+            blocked.update(0, row, col, '#');
+        }
+    }
+    assert_eq!(
+        ShapeMasks::new(&blocked, PieceSet::kanoodle()).err(),
+        Some(Error::MalformedBoard(
+            "The board does not have any open cells".to_string()
+        ))
+    );
+
+    let largest = GridLayers::new(&[(8, 8)], '·');
+    let masks = ShapeMasks::new(&largest, PieceSet::kanoodle()).unwrap();
+    assert_eq!(masks.full(), u64::MAX);
+}
@@ -0,0 +1,33 @@
+use kanoodle_solver::{BoardMask, Error};
+
+#[test]
+fn cells_in_higher_layers_rest_on_open_cells() {
+    let pyramid = "...\n...\n...\n\n..\n..\n\n.";
+    assert_eq!(
+        BoardMask::parse(pyramid, "pyramid.txt")
+            .unwrap()
+            .layer_count(),
+        3
+    );
+
+    // The cell in layer 2 only has two cells of layer 1 below it.
+    let error = BoardMask::parse("##\n##\n\n.", "floating.txt").err();
+    assert_eq!(
+        error,
+        Some(Error::InvalidBoardFile {
+            line: 4,
+            column: 1,
+            message: "The open cell does not rest on 4 open cells of layer 1".to_string(),
+        })
+    );
+
+    let error = BoardMask::parse("...\n..#\n...\n\n..\n.#", "gap.txt").err();
+    assert_eq!(
+        error,
+        Some(Error::InvalidBoardFile {
+            line: 5,
+            column: 2,
+            message: "The open cell does not rest on 4 open cells of layer 1".to_string(),
+        })
+    );
+}
@@ -0,0 +1,28 @@
+use kanoodle_solver::{BoardType, Checkpoint, Solution, Solver};
+
+fn describe(solution: Solution) -> String {
+    format!("{}\n{}", solution.placements, solution.board)
+}
+
+#[test]
+fn resumed_searches_find_the_remaining_solutions() {
+    let state = "...........\n\
+                 ...........\n\
+                 KK......LII\n\
+                 KKD....LLLI\n\
+                 DDDDJJJJLII";
+    let new_solver = || Solver::new(&BoardType::Rectangle, Some(vec![state.to_string()]), false);
+    let all: Vec<String> = new_solver().unwrap().map(describe).collect();
+    assert_eq!(all.len(), 15);
+
+    for stop_after in 0..=all.len() {
+        let mut solver = new_solver().unwrap();
+        let mut found: Vec<String> = solver.by_ref().take(stop_after).map(describe).collect();
+
+        let saved = solver.checkpoint().to_string();
+        let checkpoint: Checkpoint = saved.parse().unwrap();
+        found.extend(Solver::resume(&checkpoint).unwrap().map(describe));
+
+        assert_eq!(found, all, "stopped after {} solutions", stop_after);
+    }
+}
@@ -0,0 +1,190 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const RECTANGLE: &str = "...........\n\
+                         .......HH..\n\
+                         KK....HHLII\n\
+                         KKD...HLLLI\n\
+                         DDDDJJJJLII";
+
+/// Runs the solver with the arguments and the initial state in stdin, and returns whether it
+/// succeeded and what it wrote to stdout.
+fn output(args: &[&str], state: &str) -> (bool, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_kanoodle-solver"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(state.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+/// Runs the solver like `output`, and returns what it wrote to stdout if it succeeded.
+fn run(args: &[&str], state: &str) -> String {
+    let (succeeded, stdout) = output(args, state);
+    assert!(succeeded);
+    stdout
+}
+
+/// Checks that the record is the summary record, with the fields before the elapsed time, and
+/// that the elapsed time is a number.
+fn assert_summary(record: &str, fields: &str) {
+    let (start, elapsed) = record.split_once(r#","elapsed_seconds":"#).unwrap();
+    assert_eq!(start, format!(r#"{{"type":"summary",{}"#, fields));
+    let seconds = elapsed.strip_suffix('}').unwrap();
+    assert!(seconds.parse::<f64>().unwrap() >= 0.0);
+}
+
+#[test]
+fn ndjson_has_a_record_per_line_and_a_summary() {
+    let output = run(&["--format", "ndjson", "--stats"], RECTANGLE);
+    let records: Vec<&str> = output.lines().collect();
+    assert_eq!(records.len(), 6);
+    for record in &records[..5] {
+        assert!(record.starts_with(
+            r#"{"type":"solution","board_type":"Rectangle","path":["D[03]","H[00]","#
+        ));
+    }
+    assert_summary(
+        records[5],
+        r#""board_type":"Rectangle","solutions":5,"nodes":4644,"nodes_saved":70688"#,
+    );
+}
+
+#[test]
+fn json_is_an_array_of_records() {
+    let output = run(&["--format", "json", "--symmetry", "reduce"], RECTANGLE);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 8);
+    assert_eq!(lines[0], "[");
+    for line in &lines[1..6] {
+        assert!(line.starts_with(r#"{"type":"solution","#) && line.ends_with("]]},"));
+    }
+    assert_summary(
+        lines[6],
+        r#""board_type":"Rectangle","solutions":5,"distinct":5,"including_symmetric":5,"symmetries":1"#,
+    );
+    assert_eq!(lines[7], "]");
+}
+
+#[test]
+fn counts_by_first_piece_are_part_of_the_summary() {
+    let output = run(&["--format", "ndjson", "count"], RECTANGLE);
+    assert_summary(
+        output.trim_end(),
+        r#""board_type":"Rectangle","solutions":5,"by_first_piece":{"A[00]":2,"B[00]":2,"C[01]":1}"#,
+    );
+}
+
+#[test]
+fn generated_challenges_are_reproducible_and_have_one_solution() {
+    let challenge = run(&["--seed", "7", "generate", "--missing", "5"], "");
+    assert_eq!(
+        run(&["--seed", "7", "generate", "--missing", "5"], ""),
+        challenge
+    );
+    assert_ne!(
+        run(&["--seed", "8", "generate", "--missing", "5"], ""),
+        challenge
+    );
+    let summary = run(&["--format", "ndjson", "count"], &challenge);
+    assert!(summary.contains(r#""solutions":1,"#));
+}
+
+#[test]
+fn random_orders_count_every_solution() {
+    for seed in ["1", "2", "3"] {
+        let summary = run(
+            &["--random", "--seed", seed, "--format", "ndjson"],
+            RECTANGLE,
+        );
+        assert!(summary
+            .lines()
+            .last()
+            .unwrap()
+            .contains(r#""solutions":5,"#));
+    }
+}
+
+#[test]
+fn hints_mark_the_cells_of_the_next_piece() {
+    assert_eq!(
+        run(&["hint", "--color", "never"], RECTANGLE),
+        "Hint: place piece A (A[00])\n\
+         \x20* * * · · · · · · · · \n\
+         \x20* · · · · · · H H · · \n\
+         \x20K K · · · · H H L I I \n\
+         \x20K K D · · · H L L L I \n\
+         \x20D D D D J J J J L I I \n\
+         \n\
+         (piece A goes in the cells marked *)\n"
+    );
+}
+
+#[test]
+fn progressive_hints_reveal_one_more_piece_every_time() {
+    let file = std::env::temp_dir().join(format!("kanoodle-hints-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&file);
+    let args = [
+        "hint",
+        "--color",
+        "never",
+        "--progressive",
+        file.to_str().unwrap(),
+    ];
+
+    let first = run(&args, RECTANGLE);
+    assert!(first.starts_with("Hint: place piece A (A[00])\n"));
+    // The board is read from the file from now on.
+    let second = run(&args, "");
+    assert!(second.starts_with("Hint: place piece B (B[00])\n A A A * * * · · · · · \n"));
+    assert!(second.ends_with("Run the same command again for the next hint.\n"));
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "AAABBB.....\n\
+         A..BB..HH..\n\
+         KK....HHLII\n\
+         KKD...HLLLI\n\
+         DDDDJJJJLII\n"
+    );
+    std::fs::remove_file(&file).unwrap();
+}
+
+#[test]
+fn boards_that_cannot_be_completed_get_no_hint() {
+    // The cell inside the I can't be filled.
+    assert_eq!(
+        output(&["hint", "--color", "never"], "I.I\nIII"),
+        (
+            false,
+            "The board can't be completed. Try removing some pieces.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn colors_are_only_used_when_asked_for_or_on_a_terminal() {
+    let args = ["--limit", "1"];
+    let always = run(&[&args[..], &["--color", "always"]].concat(), RECTANGLE);
+    assert!(always.contains("\x1b[97;48;5;197m A\x1b[0m"));
+    // auto doesn't use colors, since stdout isn't a terminal.
+    for mode in [&["--color", "never"][..], &[]] {
+        let plain = run(&[&args[..], mode].concat(), RECTANGLE);
+        assert!(!plain.contains('\x1b'));
+        assert_eq!(
+            plain.lines().filter(|line| line.contains(" A A A")).count(),
+            1
+        );
+    }
+}
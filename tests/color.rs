@@ -0,0 +1,79 @@
+use kanoodle_solver::color::piece_color;
+use kanoodle_solver::{BoardMask, BoardType, ColorMode, PieceSet, Solver};
+use std::sync::Arc;
+
+/// A tray with a blocked corner, for an A tromino and a B domino.
+fn tray() -> BoardType {
+    let mask = BoardMask::parse("#..\n...", "tray.txt").unwrap();
+    BoardType::Custom(Arc::new(mask))
+        .with_pieces(PieceSet::parse("AA.B\nA..B").unwrap(), "pieces.txt")
+}
+
+/// The cell of an `A` (white on red) and a `B` (black on orange).
+const A: &str = "\x1b[97;48;5;197m A\x1b[0m";
+const B: &str = "\x1b[30;48;5;208m B\x1b[0m";
+
+#[test]
+fn every_piece_has_its_own_background_color() {
+    let board = Solver::new(&tray(), None, false)
+        .unwrap()
+        .next()
+        .unwrap()
+        .board;
+    assert_eq!(
+        board.colored().to_string(),
+        format!("  {A}{A} \n{B}{B}{A} \n")
+    );
+    // Without color the letters are written as they are.
+    assert_eq!(board.to_string(), "   A A \n B B A \n");
+}
+
+#[test]
+fn empty_cells_are_not_colored() {
+    let solver = Solver::new(&tray(), Some(vec!["#.B\n..B".to_string()]), false).unwrap();
+    let board = solver.initial_board();
+    assert_eq!(board.colored().to_string(), format!("   ·{B} \n · ·{B} \n"));
+}
+
+#[test]
+fn highlighted_pieces_are_colored_or_marked() {
+    let board = Solver::new(&tray(), None, false)
+        .unwrap()
+        .next()
+        .unwrap()
+        .board;
+    assert_eq!(
+        board.highlighted('B', true).to_string(),
+        format!("   A A \n{B}{B} A \n")
+    );
+    assert_eq!(
+        board.highlighted('B', false).to_string(),
+        "   A A \n * * A \n"
+    );
+}
+
+#[test]
+fn pieces_are_colored_in_name_order() {
+    let kanoodle = PieceSet::kanoodle();
+    assert_eq!(piece_color('A', kanoodle).rgb, "#e6194b");
+    assert_eq!(piece_color('L', kanoodle).rgb, "#469990");
+    // Letters that aren't pieces of the set are gray.
+    assert_eq!(piece_color('Z', kanoodle).rgb, "#dddddd");
+    // The first pentomino has the color of the first Kanoodle piece.
+    let pentominoes = PieceSet::pentominoes();
+    assert_eq!(
+        piece_color(pentominoes.names()[0], pentominoes),
+        piece_color('A', kanoodle)
+    );
+}
+
+#[test]
+fn color_modes_are_parsed() {
+    assert_eq!("auto".parse(), Ok(ColorMode::Auto));
+    assert_eq!("Always".parse(), Ok(ColorMode::Always));
+    assert_eq!("NEVER".parse(), Ok(ColorMode::Never));
+    assert_eq!(
+        "sometimes".parse::<ColorMode>(),
+        Err("Unknown color mode sometimes (expected auto, always or never)".to_string())
+    );
+}
@@ -0,0 +1,49 @@
+use kanoodle_solver::{BoardMask, BoardType, DlxSolver, PieceSet, Solution, Solver};
+use std::sync::Arc;
+
+/// The placements path and the board of every solution, sorted.
+fn sorted(solutions: impl Iterator<Item = Solution>) -> Vec<String> {
+    let mut solutions: Vec<String> = solutions
+        .map(|solution| format!("{}\n{}", solution.placements, solution.board))
+        .collect();
+    solutions.sort();
+    solutions
+}
+
+/// Checks that both engines find the same solutions, and returns how many there are.
+fn compare_engines(board_type: &BoardType, initial_state: Option<Vec<String>>) -> usize {
+    let dfs = sorted(Solver::new(board_type, initial_state.clone(), false).unwrap());
+    let dlx = sorted(DlxSolver::new(board_type, initial_state).unwrap());
+    assert_eq!(dfs, dlx);
+    dfs.len()
+}
+
+#[test]
+fn engines_agree_on_a_partially_filled_rectangle() {
+    let state = "...........\n\
+                 ...........\n\
+                 KK......LII\n\
+                 KKD....LLLI\n\
+                 DDDDJJJJLII";
+    let count = compare_engines(&BoardType::Rectangle, Some(vec![state.to_string()]));
+    assert_eq!(count, 15);
+}
+
+#[test]
+fn engines_agree_on_a_partially_filled_pyramid() {
+    let state = "AAABB\n\
+                 AE.BB\n\
+                 EED.B\n\
+                 EDDDD\n\
+                 EJJJJ";
+    let count = compare_engines(&BoardType::Pyramid, Some(vec![state.to_string()]));
+    assert_eq!(count, 8);
+}
+
+#[test]
+fn engines_agree_on_a_small_custom_board() {
+    let mask = BoardMask::parse("...\n...\n\n..", "small.txt").unwrap();
+    let pieces = PieceSet::parse("AA B CC\nA  B C").unwrap();
+    let board_type = BoardType::Custom(Arc::new(mask)).with_pieces(pieces, "pieces.txt");
+    assert_eq!(compare_engines(&board_type, None), 4);
+}
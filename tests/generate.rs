@@ -0,0 +1,41 @@
+use kanoodle_solver::generate::generate_challenge;
+use kanoodle_solver::{BoardType, DlxSolver, Random};
+
+#[test]
+fn challenges_have_exactly_one_solution() {
+    // Finding a random solution of the pyramid takes a while without optimizations, so it only
+    // gets one seed.
+    for (board_type, seeds) in [(BoardType::Rectangle, 1..=3), (BoardType::Pyramid, 7..=7)] {
+        for seed in seeds {
+            let challenge = generate_challenge(&board_type, 5, &mut Random::new(seed)).unwrap();
+            assert!(!challenge.missing.is_empty() && challenge.missing.len() <= 5);
+            for name in &challenge.missing {
+                assert_eq!(challenge.board.mask_of(*name), None);
+            }
+
+            // The other engine confirms the board can only be completed in one way.
+            let solutions: Vec<_> = DlxSolver::new(&board_type, Some(challenge.board.state()))
+                .unwrap()
+                .collect();
+            assert_eq!(solutions.len(), 1);
+            assert_eq!(
+                solutions[0].board.to_string(),
+                challenge.solution.board.to_string()
+            );
+        }
+    }
+}
+
+#[test]
+fn the_same_seed_generates_the_same_challenge() {
+    let generate = |seed| generate_challenge(&BoardType::Rectangle, 5, &mut Random::new(seed));
+    let first = generate(7).unwrap();
+    let second = generate(7).unwrap();
+    assert_eq!(first.board.state(), second.board.state());
+    assert_eq!(first.missing, second.missing);
+    assert_eq!(
+        first.solution.placements.to_string(),
+        second.solution.placements.to_string()
+    );
+    assert_ne!(first.board.state(), generate(8).unwrap().board.state());
+}
@@ -0,0 +1,73 @@
+use kanoodle_solver::{BoardType, Solver};
+
+/// The expected paths were found with fixed-size board layers, before they were sized at
+/// runtime. `H` is part of the initial states, since the order of its orientations changed before
+/// (and with it the orientation indexes in the paths). The other pieces are searched.
+const RECTANGLE: &str = "...........\n\
+                         .......HH..\n\
+                         KK....HHLII\n\
+                         KKD...HLLLI\n\
+                         DDDDJJJJLII";
+
+const RECTANGLE_PATHS: [&str; 5] = [
+    "D[03]; H[00]; I[03]; J[00]; K[00]; L[00]; A[00]; B[00]; C[00]; F[03]; E[03]; G[03]",
+    "D[03]; H[00]; I[03]; J[00]; K[00]; L[00]; A[00]; G[01]; F[00]; B[01]; C[01]; E[03]",
+    "D[03]; H[00]; I[03]; J[00]; K[00]; L[00]; B[00]; E[00]; C[00]; F[03]; A[03]; G[03]",
+    "D[03]; H[00]; I[03]; J[00]; K[00]; L[00]; B[00]; G[01]; C[00]; F[03]; A[01]; E[03]",
+    "D[03]; H[00]; I[03]; J[00]; K[00]; L[00]; C[01]; A[04]; F[00]; B[01]; E[02]; G[03]",
+];
+
+const PYRAMID: &str = "GGGHH\n\
+                       GEHHB\n\
+                       GEHBB\n\
+                       EEDBB\n\
+                       EDDDD";
+
+const PYRAMID_PATHS: [&str; 16] = [
+    "B[07]; D[02]; E[05]; G[00]; H[00]; C[00]; L[00]; I[03]; J[04]; A[00]; K[00]; F[01]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; C[00]; L[00]; I[03]; J[04]; K[00]; A[07]; F[01]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; C[01]; I[02]; L[00]; J[05]; A[01]; K[00]; F[00]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; C[01]; I[02]; L[00]; J[05]; A[06]; K[00]; F[00]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; C[04]; L[00]; J[03]; I[01]; A[04]; K[00]; F[02]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; C[04]; L[00]; J[03]; I[01]; K[00]; A[03]; F[02]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; C[06]; I[00]; L[00]; J[05]; A[01]; K[00]; F[00]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; C[06]; I[00]; L[00]; J[05]; A[06]; K[00]; F[00]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; I[00]; C[07]; L[00]; J[04]; A[00]; K[00]; F[01]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; I[00]; C[07]; L[00]; J[04]; K[00]; A[07]; F[01]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; I[02]; L[00]; J[03]; C[03]; A[04]; K[00]; F[02]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; I[02]; L[00]; J[03]; C[03]; K[00]; A[03]; F[02]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; J[02]; L[00]; C[05]; I[01]; A[05]; K[00]; F[03]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; J[02]; L[00]; C[05]; I[01]; K[00]; A[02]; F[03]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; J[02]; L[00]; I[03]; C[02]; A[05]; K[00]; F[03]",
+    "B[07]; D[02]; E[05]; G[00]; H[00]; J[02]; L[00]; I[03]; C[02]; K[00]; A[02]; F[03]",
+];
+
+/// The placements path of every solution, in the order they were found.
+fn paths(board_type: &BoardType, state: &str) -> Vec<String> {
+    Solver::new(board_type, Some(vec![state.to_string()]), false)
+        .unwrap()
+        .map(|solution| {
+            solution
+                .placements
+                .to_string()
+                .trim_end_matches("; ")
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn solutions_of_a_partially_filled_rectangle_are_found_in_the_same_order() {
+    assert_eq!(
+        paths(&BoardType::Rectangle, RECTANGLE),
+        RECTANGLE_PATHS.map(String::from)
+    );
+}
+
+#[test]
+fn solutions_of_a_partially_filled_pyramid_are_found_in_the_same_order() {
+    assert_eq!(
+        paths(&BoardType::Pyramid, PYRAMID),
+        PYRAMID_PATHS.map(String::from)
+    );
+}
@@ -0,0 +1,55 @@
+use kanoodle_solver::output::json_string;
+use kanoodle_solver::{BoardMask, BoardType, PieceSet, Solver};
+use std::sync::Arc;
+
+#[test]
+fn json_strings_are_escaped() {
+    assert_eq!(json_string("A[00]"), r#""A[00]""#);
+    assert_eq!(json_string(r#"tray "2x3".txt"#), r#""tray \"2x3\".txt""#);
+    assert_eq!(json_string(r"C:\boards"), r#""C:\\boards""#);
+    assert_eq!(json_string("AAB\r\nA\tB"), r#""AAB\r\nA\tB""#);
+    assert_eq!(json_string("\u{1}·"), r#""\u0001·""#);
+}
+
+#[test]
+fn solutions_are_json_records() {
+    let mask = BoardMask::parse("...\n...", r#"tray "2x3".txt"#).unwrap();
+    let trominoes = BoardType::Custom(Arc::new(mask))
+        .with_pieces(PieceSet::parse("AA.B\nA.BB").unwrap(), "trominoes.txt");
+    let solution = Solver::new(&trominoes, None, false)
+        .unwrap()
+        .next()
+        .unwrap();
+    assert_eq!(
+        solution.to_json(),
+        concat!(
+            r#"{"type":"solution","#,
+            r#""board_type":"CustomPieces(Custom(tray \"2x3\".txt) with trominoes.txt)","#,
+            r#""path":["A[00]","B[03]"],"#,
+            r#""pieces":["#,
+            r#"{"name":"A","orientation":0,"cells":[[0,0,0],[0,0,1],[0,1,0]]},"#,
+            r#"{"name":"B","orientation":3,"cells":[[0,0,2],[0,1,1],[0,1,2]]}"#,
+            r#"],"#,
+            r#""grid":[["AAB","ABB"]]}"#
+        )
+    );
+}
+
+#[test]
+fn pyramid_records_have_every_layer() {
+    let state = "AAABB\n\
+                 AE.BB\n\
+                 EED.B\n\
+                 EDDDD\n\
+                 EJJJJ";
+    let solution = Solver::new(&BoardType::Pyramid, Some(vec![state.to_string()]), false)
+        .unwrap()
+        .next()
+        .unwrap();
+    let json = solution.to_json();
+    assert!(json.starts_with(r#"{"type":"solution","board_type":"Pyramid","path":["A[00]","#));
+    assert!(json.contains(r#"{"name":"H","orientation":4,"cells":[[2,0,2],"#));
+    assert!(json.ends_with(
+        r#""grid":[["AAABB","AELBB","EEDLB","EDDDD","EJJJJ"],["CCCC","IILC","IFKK","IIKK"],["GLH","FHL","HFG"],["GH","HG"],["G"]]}"#
+    ));
+}
@@ -0,0 +1,36 @@
+use kanoodle_solver::{BoardType, ParallelSolver, Solution, Solver};
+
+/// The placements path and the board of every solution, in the order they were found.
+fn in_order(solutions: impl Iterator<Item = Solution>) -> Vec<String> {
+    solutions
+        .map(|solution| format!("{}\n{}", solution.placements, solution.board))
+        .collect()
+}
+
+fn solver() -> Solver {
+    let state = "...........\n\
+                 ...........\n\
+                 KK......LII\n\
+                 KKD....LLLI\n\
+                 DDDDJJJJLII";
+    Solver::new(&BoardType::Rectangle, Some(vec![state.to_string()]), false).unwrap()
+}
+
+#[test]
+fn threads_find_the_same_solutions_in_the_same_order() {
+    let expected = in_order(solver());
+    assert_eq!(expected.len(), 15);
+    for threads in 1..=4 {
+        for split_depth in 0..=3 {
+            let parallel = ParallelSolver::new(solver(), threads, split_depth);
+            assert_eq!(in_order(parallel), expected);
+        }
+    }
+}
+
+#[test]
+fn workers_stop_when_the_solver_is_dropped() {
+    let expected = in_order(solver().take(2));
+    let parallel = ParallelSolver::new(solver(), 4, 3);
+    assert_eq!(in_order(parallel.take(2)), expected);
+}
@@ -0,0 +1,33 @@
+use kanoodle_solver::{Board, BoardType, DlxSolver, Symmetry};
+use std::sync::Arc;
+
+/// Counts the solutions, not counting mirror images and rotations of a solution.
+fn count_distinct(board_type: &BoardType) -> usize {
+    let symmetry = Arc::new(Symmetry::new(&Board::new(board_type)));
+    DlxSolver::new(board_type, None)
+        .unwrap()
+        .with_symmetry(symmetry)
+        .count()
+}
+
+#[test]
+fn pentomino_5x12_has_1010_solutions() {
+    assert_eq!(count_distinct(&BoardType::Pentomino5x12), 1010);
+}
+
+#[test]
+fn pentomino_4x15_has_368_solutions() {
+    assert_eq!(count_distinct(&BoardType::Pentomino4x15), 368);
+}
+
+#[test]
+fn pentomino_3x20_has_2_solutions() {
+    assert_eq!(count_distinct(&BoardType::Pentomino3x20), 2);
+}
+
+/// Takes a while without optimizations: `cargo test --release -- --ignored`
+#[test]
+#[ignore]
+fn pentomino_6x10_has_2339_solutions() {
+    assert_eq!(count_distinct(&BoardType::Pentomino6x10), 2339);
+}
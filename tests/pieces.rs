@@ -0,0 +1,49 @@
+use kanoodle_solver::{Board, BoardMask, BoardType, DlxSolver, Error, PieceSet, Solver, Symmetry};
+use std::sync::Arc;
+
+#[test]
+fn board_types_with_different_pieces_are_solved_in_one_process() {
+    let pentominoes = BoardType::Pentomino3x20;
+    let symmetry = Arc::new(Symmetry::new(&Board::new(&pentominoes)));
+    let solver = DlxSolver::new(&pentominoes, None)
+        .unwrap()
+        .with_symmetry(symmetry);
+    assert_eq!(solver.count(), 2);
+
+    // Two L trominoes fill a 2 x 3 rectangle in two ways, and either one can be on the left.
+    let mask = BoardMask::parse("...\n...", "2x3.txt").unwrap();
+    let trominoes = BoardType::Custom(Arc::new(mask))
+        .with_pieces(PieceSet::parse("AA.B\nA.BB").unwrap(), "trominoes.txt");
+    assert_eq!(Solver::new(&trominoes, None, false).unwrap().count(), 4);
+    assert_eq!(DlxSolver::new(&trominoes, None).unwrap().count(), 4);
+}
+
+#[test]
+fn pieces_that_cannot_fill_the_board_are_rejected() {
+    let mask = BoardMask::parse("...\n...", "2x3.txt").unwrap();
+    let kanoodle = BoardType::Custom(Arc::new(mask));
+    let mismatch = Error::CellCountMismatch {
+        open_cells: 6,
+        piece_cells: 55,
+    };
+    assert_eq!(
+        Solver::new(&kanoodle, None, false).err(),
+        Some(mismatch.clone())
+    );
+    assert_eq!(DlxSolver::new(&kanoodle, None).err(), Some(mismatch));
+}
+
+#[test]
+fn single_cell_pieces_are_placed_once() {
+    let mask = BoardMask::parse("..", "2x1.txt").unwrap();
+    let monominoes = BoardType::Custom(Arc::new(mask))
+        .with_pieces(PieceSet::parse("A B").unwrap(), "monominoes.txt");
+    assert_eq!(Solver::new(&monominoes, None, false).unwrap().count(), 2);
+    assert_eq!(DlxSolver::new(&monominoes, None).unwrap().count(), 2);
+
+    let mask = BoardMask::parse(".", "1x1.txt").unwrap();
+    let monomino = BoardType::Custom(Arc::new(mask))
+        .with_pieces(PieceSet::parse("A").unwrap(), "monomino.txt");
+    assert_eq!(Solver::new(&monomino, None, false).unwrap().count(), 1);
+    assert_eq!(monomino.pieces()[&'A'].orientations.len(), 1);
+}
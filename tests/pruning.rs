@@ -0,0 +1,64 @@
+use kanoodle_solver::{BoardType, ParallelSolver, Solution, Solver};
+
+const RECTANGLE: &str = "...........\n\
+                         ...........\n\
+                         KK......LII\n\
+                         KKD....LLLI\n\
+                         DDDDJJJJLII";
+
+const PYRAMID: &str = "AAABB\n\
+                       AE.BB\n\
+                       EED.B\n\
+                       EDDDD\n\
+                       EJJJJ";
+
+/// The placements path and the board of every solution, in the order they were found.
+fn in_order(solutions: impl Iterator<Item = Solution>) -> Vec<String> {
+    solutions
+        .map(|solution| format!("{}\n{}", solution.placements, solution.board))
+        .collect()
+}
+
+fn solver(board_type: &BoardType, state: &str, pruning: bool) -> Solver {
+    Solver::new(board_type, Some(vec![state.to_string()]), false)
+        .unwrap()
+        .with_dead_region_pruning(pruning)
+}
+
+#[test]
+fn pruning_finds_the_same_solutions_in_the_same_order() {
+    for (board_type, state, count) in [
+        (BoardType::Rectangle, RECTANGLE, 15),
+        (BoardType::Pyramid, PYRAMID, 8),
+    ] {
+        let pruned = in_order(solver(&board_type, state, true));
+        let unpruned = in_order(solver(&board_type, state, false));
+        assert_eq!(pruned.len(), count);
+        assert_eq!(pruned, unpruned);
+    }
+}
+
+#[test]
+fn saved_nodes_add_up_to_the_nodes_of_a_search_without_pruning() {
+    for (board_type, state) in [
+        (BoardType::Rectangle, RECTANGLE),
+        (BoardType::Pyramid, PYRAMID),
+    ] {
+        let unpruned = solver(&board_type, state, false);
+        let unpruned_stats = unpruned.stats();
+        unpruned.for_each(drop);
+
+        let pruned = solver(&board_type, state, true).with_saved_nodes_counted();
+        let stats = pruned.stats();
+        let solutions = in_order(pruned);
+        assert!(stats.saved() > 0);
+        assert_eq!(stats.nodes() + stats.saved(), unpruned_stats.nodes());
+
+        // Splitting the search into subtrees doesn't change the counts.
+        let pruned = solver(&board_type, state, true).with_saved_nodes_counted();
+        let parallel_stats = pruned.stats();
+        assert_eq!(in_order(ParallelSolver::new(pruned, 3, 2)), solutions);
+        assert_eq!(parallel_stats.nodes(), stats.nodes());
+        assert_eq!(parallel_stats.saved(), stats.saved());
+    }
+}
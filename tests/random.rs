@@ -0,0 +1,72 @@
+use kanoodle_solver::{BoardType, Random, SearchOrder, Solution, Solver};
+use std::sync::Arc;
+
+/// The board of every solution, in the order they were found. The placements paths are left
+/// out, since the pieces are placed in a different order.
+fn boards(solutions: impl Iterator<Item = Solution>) -> Vec<String> {
+    solutions
+        .map(|solution| solution.board.to_string())
+        .collect()
+}
+
+fn solver(board_type: &BoardType, state: &str, seed: Option<u64>) -> Solver {
+    let solver = Solver::new(board_type, Some(vec![state.to_string()]), false).unwrap();
+    match seed {
+        Some(seed) => {
+            let order = SearchOrder::random(board_type.pieces(), &mut Random::new(seed));
+            solver.with_search_order(Arc::new(order))
+        }
+        None => solver,
+    }
+}
+
+#[test]
+fn random_orders_find_the_same_solutions() {
+    for (board_type, state, count) in [
+        (
+            BoardType::Rectangle,
+            "...........\n\
+             ...........\n\
+             KK......LII\n\
+             KKD....LLLI\n\
+             DDDDJJJJLII",
+            15,
+        ),
+        (
+            BoardType::Pyramid,
+            "AAABB\n\
+             AE.BB\n\
+             EED.B\n\
+             EDDDD\n\
+             EJJJJ",
+            8,
+        ),
+    ] {
+        let lexical = boards(solver(&board_type, state, None));
+        assert_eq!(lexical.len(), count);
+        let mut sorted_lexical = lexical.clone();
+        sorted_lexical.sort();
+
+        let mut reordered = false;
+        for seed in 0..8 {
+            let random = boards(solver(&board_type, state, Some(seed)));
+            reordered |= random != lexical;
+            let mut sorted_random = random;
+            sorted_random.sort();
+            assert_eq!(sorted_random, sorted_lexical);
+        }
+        assert!(reordered);
+    }
+}
+
+#[test]
+fn the_same_seed_finds_the_solutions_in_the_same_order() {
+    let state = "...........\n\
+                 ...........\n\
+                 KK......LII\n\
+                 KKD....LLLI\n\
+                 DDDDJJJJLII";
+    let first = boards(solver(&BoardType::Rectangle, state, Some(3)));
+    let second = boards(solver(&BoardType::Rectangle, state, Some(3)));
+    assert_eq!(first, second);
+}
@@ -0,0 +1,76 @@
+use kanoodle_solver::rate::rate;
+use kanoodle_solver::{BoardType, Difficulty, Rating, Solver};
+
+fn rating(state: &str) -> Rating {
+    rate(Solver::new(&BoardType::Rectangle, Some(vec![state.to_string()]), false).unwrap())
+}
+
+/// The branching factors, rounded to two decimals.
+fn rounded(branching: &[f64]) -> Vec<f64> {
+    branching
+        .iter()
+        .map(|branching| (branching * 100.0).round() / 100.0)
+        .collect()
+}
+
+#[test]
+fn a_position_with_seven_missing_pieces_is_an_expert_challenge() {
+    let rating = rating(
+        "...........\n\
+         ...........\n\
+         KK......LII\n\
+         KKD....LLLI\n\
+         DDDDJJJJLII",
+    );
+    assert_eq!(rating.solutions, 15);
+    assert_eq!(rating.nodes_to_first_solution, Some(24));
+    assert_eq!(rating.nodes, 45736);
+    assert_eq!(rating.dead_ends, 8860);
+    assert_eq!(
+        rounded(&rating.branching),
+        [9.0, 13.89, 6.08, 1.31, 0.49, 0.42, 0.07]
+    );
+    assert_eq!(format!("{:.1}", rating.score().unwrap()), "20.6");
+    assert_eq!(rating.difficulty(), Some(Difficulty::Expert));
+}
+
+#[test]
+fn a_position_with_six_missing_pieces_is_a_junior_challenge() {
+    let rating = rating(
+        "...........\n\
+         .......HH..\n\
+         KK....HHLII\n\
+         KKD...HLLLI\n\
+         DDDDJJJJLII",
+    );
+    assert_eq!(rating.solutions, 5);
+    assert_eq!(rating.nodes_to_first_solution, Some(23));
+    assert_eq!(rating.nodes, 4644);
+    assert_eq!(rating.dead_ends, 776);
+    assert_eq!(
+        rounded(&rating.branching),
+        [9.0, 9.22, 1.08, 0.32, 0.59, 0.29]
+    );
+    assert_eq!(format!("{:.1}", rating.score().unwrap()), "15.2");
+    assert_eq!(rating.difficulty(), Some(Difficulty::Junior));
+}
+
+#[test]
+fn positions_without_solutions_are_not_rated() {
+    // The cell inside the I can't be filled.
+    let rating = rating("I.I\nIII");
+    assert_eq!(rating.solutions, 0);
+    assert_eq!(rating.nodes_to_first_solution, None);
+    assert_eq!(rating.score(), None);
+    assert_eq!(rating.difficulty(), None);
+}
+
+#[test]
+fn scores_are_divided_into_difficulties() {
+    assert_eq!(Difficulty::of(0.0), Difficulty::Starter);
+    assert_eq!(Difficulty::of(7.9), Difficulty::Starter);
+    assert_eq!(Difficulty::of(8.0), Difficulty::Junior);
+    assert_eq!(Difficulty::of(16.0), Difficulty::Expert);
+    assert_eq!(Difficulty::of(23.9), Difficulty::Expert);
+    assert_eq!(Difficulty::of(24.0), Difficulty::Master);
+}
@@ -0,0 +1,69 @@
+use kanoodle_solver::svg::stacked_svg;
+use kanoodle_solver::{Board, BoardMask, BoardType, PieceSet, Solver};
+use std::sync::Arc;
+
+/// A tray with a blocked corner, for an A tromino and a B domino.
+fn tray() -> BoardType {
+    let mask = BoardMask::parse("#..\n...", "tray.txt").unwrap();
+    BoardType::Custom(Arc::new(mask))
+        .with_pieces(PieceSet::parse("AA.B\nA..B").unwrap(), "pieces.txt")
+}
+
+#[test]
+fn empty_cells_are_drawn_as_outlines_and_blocked_cells_are_left_out() {
+    let solver = Solver::new(&tray(), Some(vec!["#.B\n..B".to_string()]), false).unwrap();
+    assert_eq!(
+        solver.initial_board().to_svg(),
+        include_str!("snapshots/tray.svg")
+    );
+}
+
+#[test]
+fn pyramid_layers_are_drawn_next_to_each_other() {
+    let state = "AAABB\n\
+                 AE.BB\n\
+                 EED.B\n\
+                 EDDDD\n\
+                 EJJJJ";
+    let solution = Solver::new(&BoardType::Pyramid, Some(vec![state.to_string()]), false)
+        .unwrap()
+        .next()
+        .unwrap();
+    assert_eq!(
+        solution.board.to_svg(),
+        include_str!("snapshots/pyramid.svg")
+    );
+}
+
+#[test]
+fn stacked_boards_are_drawn_below_their_captions() {
+    let boards: Vec<(String, Board)> = Solver::new(&tray(), None, false)
+        .unwrap()
+        .map(|solution| (solution.placements.to_string(), solution.board))
+        .collect();
+    assert_eq!(stacked_svg(&boards), include_str!("snapshots/stacked.svg"));
+}
+
+#[test]
+fn captions_are_escaped() {
+    let board = Solver::new(&tray(), None, false)
+        .unwrap()
+        .next()
+        .unwrap()
+        .board;
+    let svg = stacked_svg(&[(r#"<A & "B's">"#.to_string(), board)]);
+    assert!(svg.contains(r#"font-size="14">&lt;A &amp; &quot;B&#39;s&quot;&gt;</text>"#));
+}
+
+#[test]
+fn no_boards_make_an_empty_image() {
+    assert_eq!(
+        stacked_svg(&[]),
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="40" viewBox="0 0 40 40">"#,
+            "\n",
+            r#"<rect width="40" height="40" fill="white"/>"#,
+            "\n</svg>\n"
+        )
+    );
+}
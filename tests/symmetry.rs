@@ -0,0 +1,83 @@
+use kanoodle_solver::state::parse_state;
+use kanoodle_solver::{BoardType, DlxSolver, Solution, Solver, Symmetry};
+use std::sync::Arc;
+
+/// Both mirrored halves of the rectangle are open apart from the `L` and the `I`, which are
+/// mirrored onto themselves.
+const RECTANGLE: &str = "...........\n\
+                         .....L.II..\n\
+                         ....LLLI...\n\
+                         .....L.II..\n\
+                         ...........";
+
+/// Every piece lies on the diagonal of its layers, so swapping the rows and columns maps the
+/// pyramid onto itself.
+const PYRAMID: &str = "GGG..\n\
+                       G....\n\
+                       G....\n\
+                       .....\n\
+                       .....\n\
+                       \n\
+                       ....\n\
+                       ..L.\n\
+                       .LLL\n\
+                       ..LJ\n\
+                       \n\
+                       ...\n\
+                       ...\n\
+                       ..J\n\
+                       \n\
+                       FF\n\
+                       FJ\n\
+                       \n\
+                       J";
+
+/// The placements path and the board of every solution, sorted.
+fn sorted(solutions: impl Iterator<Item = Solution>) -> Vec<String> {
+    let mut solutions: Vec<String> = solutions
+        .map(|solution| format!("{}\n{}", solution.placements, solution.board))
+        .collect();
+    solutions.sort();
+    solutions
+}
+
+/// Checks that the canonical solutions of both engines account for every solution of the
+/// unrestricted search, both when counting the symmetric solutions and when deriving them.
+fn check_symmetry(board_type: &BoardType, state: &str, distinct: usize, raw: usize) {
+    let state = Some(parse_state(state, board_type).unwrap());
+    let unrestricted = sorted(DlxSolver::new(board_type, state.clone()).unwrap());
+    assert_eq!(unrestricted.len(), raw);
+
+    let dfs = Solver::new(board_type, state.clone(), false).unwrap();
+    let symmetry = Arc::new(Symmetry::new(dfs.initial_board()));
+    assert!(symmetry.transformation_count() > 1);
+    let dfs: Vec<Solution> = dfs.with_symmetry(Arc::clone(&symmetry)).collect();
+    let dlx: Vec<Solution> = DlxSolver::new(board_type, state)
+        .unwrap()
+        .with_symmetry(Arc::clone(&symmetry))
+        .collect();
+
+    for reduced in [dfs, dlx] {
+        assert_eq!(reduced.len(), distinct);
+        let class_sizes: usize = reduced
+            .iter()
+            .map(|solution| symmetry.class_size(&solution.board))
+            .sum();
+        assert_eq!(class_sizes, raw);
+
+        let expanded = reduced
+            .into_iter()
+            .flat_map(|solution| symmetry.expand(solution));
+        assert_eq!(sorted(expanded), unrestricted);
+    }
+}
+
+#[test]
+fn symmetric_solutions_of_a_partially_filled_rectangle_are_all_found() {
+    check_symmetry(&BoardType::Rectangle, RECTANGLE, 42, 84);
+}
+
+#[test]
+fn symmetric_solutions_of_a_partially_filled_pyramid_are_all_found() {
+    check_symmetry(&BoardType::Pyramid, PYRAMID, 12, 24);
+}
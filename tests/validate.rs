@@ -0,0 +1,64 @@
+use kanoodle_solver::solver::check_pieces;
+use kanoodle_solver::{BoardType, DlxSolver, Error};
+
+fn errors(board_type: &BoardType, state: &str) -> Vec<(char, Error)> {
+    let state: Vec<String> = state.split("\n\n").map(str::to_string).collect();
+    check_pieces(board_type, &state)
+        .1
+        .into_iter()
+        .filter_map(|(name, check)| check.err().map(|e| (name, e)))
+        .collect()
+}
+
+#[test]
+fn every_piece_of_a_solution_is_valid() {
+    for board_type in [BoardType::Rectangle, BoardType::Pyramid] {
+        for solution in DlxSolver::new(&board_type, None).unwrap().take(20) {
+            let state = solution.board.state();
+            let (board, pieces) = check_pieces(&board_type, &state);
+            assert!(board.solved());
+            let mut path = solution.placements.path();
+            path.sort();
+            let checked: Vec<_> = pieces.into_iter().map(|(_, p)| p.unwrap()).collect();
+            assert_eq!(checked, path);
+        }
+    }
+}
+
+#[test]
+fn letters_used_for_more_than_one_piece_are_duplicates() {
+    let rectangle = BoardType::Rectangle;
+    // Fewer cells than the piece has, in two groups.
+    assert_eq!(
+        errors(&rectangle, "A.A"),
+        vec![('A', Error::DuplicatePiece('A'))]
+    );
+    // As many cells as the piece has, in two groups.
+    assert_eq!(
+        errors(&rectangle, "AA.AA"),
+        vec![('A', Error::DuplicatePiece('A'))]
+    );
+    // More cells than the piece has, in one group.
+    assert_eq!(
+        errors(&rectangle, "AAAAA"),
+        vec![('A', Error::DuplicatePiece('A'))]
+    );
+    // One group of cells in the wrong shape is a single piece drawn wrong.
+    assert!(matches!(
+        errors(&rectangle, "AAA").as_slice(),
+        [('A', Error::OrientationNotFound { .. })]
+    ));
+
+    // Cells in different layers touch if one rests on the other.
+    let pyramid = BoardType::Pyramid;
+    let resting = "A\n\nA";
+    assert!(matches!(
+        errors(&pyramid, resting).as_slice(),
+        [('A', Error::OrientationNotFound { .. })]
+    ));
+    let apart = "A\n\n.A";
+    assert_eq!(
+        errors(&pyramid, apart),
+        vec![('A', Error::DuplicatePiece('A'))]
+    );
+}